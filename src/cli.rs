@@ -0,0 +1,544 @@
+use smart_road::sim::{
+    Grid, LEVEL_NAMES, LaneLayout, Level, RunLimit, SignalTimings, TrajectoryFormat,
+    policy_from_name,
+};
+
+/// Longest headless run accepted by `--seconds`, a simulated year.
+const MAX_SECONDS: f32 = 365.0 * 24.0 * 3600.0;
+
+/// Command line options for the simulation binary.
+#[derive(Clone, Debug)]
+pub struct Options {
+    pub headless: bool,
+    /// Headless run length; defaults to 60 s, or the recorded length for a replay.
+    pub limit: Option<RunLimit>,
+    /// Seed for the simulation RNG, drawn at random when not given.
+    pub seed: Option<u64>,
+    /// Name of the intersection policy, see `POLICY_NAMES`. Defaults to
+    /// `occupancy`, or to the recorded policy for a replay.
+    pub policy: Option<String>,
+    /// Path of a scenario file to play back.
+    pub scenario: Option<String>,
+    /// Inbound lanes per approach and their uses. Defaults to the scenario's
+    /// layout, else `r,s,l`; a replay keeps the recorded one.
+    pub lanes: Option<LaneLayout>,
+    /// Grid of intersections to run instead of a single one.
+    pub grid: Option<Grid>,
+    /// Recorded session to play back instead of live input.
+    pub replay: Option<String>,
+    /// Where the window session's inputs are saved on exit, when they are recorded.
+    pub record: Option<String>,
+    /// File (or `-` for stdout) receiving per-tick vehicle states.
+    pub trajectory: Option<String>,
+    /// Trajectory format, guessed from the file name when not given.
+    pub trajectory_format: Option<TrajectoryFormat>,
+    /// Trajectory sampling interval in ticks.
+    pub sample_every: u64,
+    /// File receiving the statistics and run metadata on exit (`.json` or `.csv`).
+    pub stats_out: Option<String>,
+    /// File receiving one record per vehicle journey on exit (`.csv` or `.jsonl`).
+    pub journeys_out: Option<String>,
+    /// File receiving the per-second time series on exit (`.csv` or `.jsonl`).
+    pub series_out: Option<String>,
+    /// Lowest level of events printed on stderr, `None` for none at all.
+    pub log_level: Option<Level>,
+    /// File (or `-` for stderr) receiving the event stream.
+    pub events: Option<String>,
+    /// Lowest level of events written to the `events` file.
+    pub events_level: Level,
+    /// Time-to-collision below which a pair is a close call (s).
+    pub ttc: Option<f32>,
+    /// Post-encroachment time below which a pair is a close call (s).
+    pub pet: Option<f32>,
+    /// Signal durations for the traffic light policies; unset ones come from
+    /// the scenario or the recording, else the plan's defaults.
+    pub signals: SignalTimings,
+    /// Stop at the first collision: pause the window, end a headless run.
+    pub halt_on_collision: bool,
+}
+
+impl Options {
+    /**
+     * Parses the command line arguments (without the program name).
+     *
+     * # Returns
+     *
+     * A Result containing the parsed options or an error string.
+     */
+    pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<Self, String> {
+        let mut options = Options {
+            headless: false,
+            limit: None,
+            seed: None,
+            policy: None,
+            scenario: None,
+            lanes: None,
+            grid: None,
+            replay: None,
+            record: None,
+            trajectory: None,
+            trajectory_format: None,
+            sample_every: 1,
+            stats_out: None,
+            journeys_out: None,
+            series_out: None,
+            log_level: Some(Level::Warn),
+            events: None,
+            events_level: Level::Debug,
+            ttc: None,
+            pet: None,
+            signals: SignalTimings::default(),
+            halt_on_collision: false,
+        };
+
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--headless" => options.headless = true,
+                "--halt-on-collision" => options.halt_on_collision = true,
+                "--ticks" => {
+                    let ticks = next_value(&mut args, &arg)?;
+                    options.limit =
+                        Some(RunLimit::Ticks(ticks.parse().map_err(|_| {
+                            format!("invalid value for --ticks: {}", ticks)
+                        })?));
+                }
+                "--seconds" => {
+                    let seconds = next_value(&mut args, &arg)?;
+                    options.limit = Some(RunLimit::Seconds(parse_seconds(&seconds)?));
+                }
+                "--seed" => {
+                    let seed = next_value(&mut args, &arg)?;
+                    options.seed = Some(
+                        seed.parse()
+                            .map_err(|_| format!("invalid value for --seed: {}", seed))?,
+                    );
+                }
+                "--policy" => {
+                    options.policy = Some(next_value(&mut args, &arg)?);
+                }
+                "--scenario" => options.scenario = Some(next_value(&mut args, &arg)?),
+                "--lanes" => {
+                    let lanes = next_value(&mut args, &arg)?;
+                    options.lanes = Some(
+                        LaneLayout::parse(&lanes)
+                            .map_err(|e| format!("invalid value for --lanes: {}", e))?,
+                    );
+                }
+                "--grid" => {
+                    let grid = next_value(&mut args, &arg)?;
+                    options.grid = Some(
+                        Grid::parse(&grid)
+                            .map_err(|e| format!("invalid value for --grid: {}", e))?,
+                    );
+                }
+                "--replay" => options.replay = Some(next_value(&mut args, &arg)?),
+                "--record" => options.record = Some(next_value(&mut args, &arg)?),
+                "--trajectory" => options.trajectory = Some(next_value(&mut args, &arg)?),
+                "--trajectory-format" => {
+                    let format = next_value(&mut args, &arg)?;
+                    options.trajectory_format = Some(TrajectoryFormat::from_name(&format)?);
+                }
+                "--sample-every" => {
+                    let ticks = next_value(&mut args, &arg)?;
+                    options.sample_every = ticks
+                        .parse()
+                        .ok()
+                        .filter(|&ticks| ticks > 0)
+                        .ok_or_else(|| format!("invalid value for --sample-every: {}", ticks))?;
+                }
+                "--stats-out" => options.stats_out = Some(next_value(&mut args, &arg)?),
+                "--journeys-out" => options.journeys_out = Some(next_value(&mut args, &arg)?),
+                "--series-out" => options.series_out = Some(next_value(&mut args, &arg)?),
+                "--log-level" => {
+                    let level = next_value(&mut args, &arg)?;
+                    options.log_level = match level.as_str() {
+                        "off" => None,
+                        name => Some(parse_level(name, &arg)?),
+                    };
+                }
+                "--events" => options.events = Some(next_value(&mut args, &arg)?),
+                "--events-level" => {
+                    options.events_level = parse_level(&next_value(&mut args, &arg)?, &arg)?
+                }
+                "--ttc" => {
+                    options.ttc = Some(parse_threshold(&next_value(&mut args, &arg)?, &arg)?)
+                }
+                "--pet" => {
+                    options.pet = Some(parse_threshold(&next_value(&mut args, &arg)?, &arg)?)
+                }
+                "--green" => {
+                    options.signals.green =
+                        Some(parse_threshold(&next_value(&mut args, &arg)?, &arg)?)
+                }
+                "--left-green" => {
+                    options.signals.left_green =
+                        Some(parse_threshold(&next_value(&mut args, &arg)?, &arg)?)
+                }
+                "--yellow" => {
+                    options.signals.yellow =
+                        Some(parse_threshold(&next_value(&mut args, &arg)?, &arg)?)
+                }
+                "--all-red" => {
+                    options.signals.all_red =
+                        Some(parse_duration(&next_value(&mut args, &arg)?, &arg)?)
+                }
+                "--max-green" => {
+                    options.signals.max_green =
+                        Some(parse_threshold(&next_value(&mut args, &arg)?, &arg)?)
+                }
+                _ => return Err(format!("unknown argument: {}", arg)),
+            }
+        }
+
+        if let Some(policy) = &options.policy {
+            policy_from_name(policy, &options.signals)?;
+        }
+        if options.replay.is_some()
+            && (options.scenario.is_some() || options.seed.is_some() || options.lanes.is_some())
+        {
+            return Err(
+                "--replay takes its seed, scenario and lanes from the replay file".to_string(),
+            );
+        }
+        if options.record.is_some() && (options.headless || options.replay.is_some()) {
+            return Err(
+                "--record records window sessions and cannot be combined with \
+                 --headless or --replay"
+                    .to_string(),
+            );
+        }
+        if options.grid.is_some()
+            && (options.scenario.is_some()
+                || options.replay.is_some()
+                || options.record.is_some()
+                || options.journeys_out.is_some()
+                || options.series_out.is_some())
+        {
+            return Err(
+                "--grid cannot be combined with --scenario, --replay, --record, \
+                 --journeys-out or --series-out"
+                    .to_string(),
+            );
+        }
+
+        Ok(options)
+    }
+}
+
+fn parse_level(name: &str, flag: &str) -> Result<Level, String> {
+    Level::from_name(name).ok_or_else(|| {
+        format!(
+            "invalid value for {}: {} (expected one of {})",
+            flag,
+            name,
+            LEVEL_NAMES.join(", ")
+        )
+    })
+}
+
+fn parse_seconds(value: &str) -> Result<f32, String> {
+    value
+        .parse::<f32>()
+        .ok()
+        .filter(|seconds| seconds.is_finite() && *seconds > 0.0 && *seconds <= MAX_SECONDS)
+        .ok_or_else(|| {
+            format!(
+                "invalid value for --seconds: {} (expected a number of seconds above 0 and up to {})",
+                value, MAX_SECONDS
+            )
+        })
+}
+
+fn parse_threshold(value: &str, flag: &str) -> Result<f32, String> {
+    value
+        .parse::<f32>()
+        .ok()
+        .filter(|seconds| seconds.is_finite() && *seconds > 0.0)
+        .ok_or_else(|| format!("invalid value for {}: {}", flag, value))
+}
+
+fn parse_duration(value: &str, flag: &str) -> Result<f32, String> {
+    value
+        .parse::<f32>()
+        .ok()
+        .filter(|seconds| seconds.is_finite() && *seconds >= 0.0)
+        .ok_or_else(|| format!("invalid value for {}: {}", flag, value))
+}
+
+fn next_value<I: Iterator<Item = String>>(args: &mut I, flag: &str) -> Result<String, String> {
+    args.next()
+        .ok_or_else(|| format!("missing value for {}", flag))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Options, String> {
+        Options::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn defaults_to_the_window() {
+        let options = parse(&[]).unwrap();
+        assert!(!options.headless);
+        assert_eq!(options.limit, None);
+        assert_eq!(options.policy, None);
+        assert_eq!(options.seed, None);
+    }
+
+    #[test]
+    fn reads_the_seed() {
+        assert_eq!(parse(&["--seed", "42"]).unwrap().seed, Some(42));
+        assert_eq!(
+            parse(&["--seed", "-3"]).unwrap_err(),
+            "invalid value for --seed: -3"
+        );
+    }
+
+    #[test]
+    fn reads_the_headless_limit_the_last_one_winning() {
+        let options = parse(&["--headless", "--ticks", "600"]).unwrap();
+        assert!(options.headless);
+        assert!(!options.halt_on_collision);
+        assert_eq!(options.limit, Some(RunLimit::Ticks(600)));
+        let options = parse(&["--ticks", "600", "--seconds", "2.5"]).unwrap();
+        assert_eq!(options.limit, Some(RunLimit::Seconds(2.5)));
+    }
+
+    #[test]
+    fn rejects_missing_invalid_and_unknown_arguments() {
+        assert_eq!(
+            parse(&["--ticks"]).unwrap_err(),
+            "missing value for --ticks"
+        );
+        assert_eq!(
+            parse(&["--ticks", "-1"]).unwrap_err(),
+            "invalid value for --ticks: -1"
+        );
+        assert!(
+            parse(&["--seconds", "soon"])
+                .unwrap_err()
+                .starts_with("invalid value for --seconds: soon (expected")
+        );
+        assert_eq!(parse(&["--fast"]).unwrap_err(), "unknown argument: --fast");
+    }
+
+    #[test]
+    fn seconds_must_be_positive_finite_and_at_most_a_year() {
+        assert_eq!(
+            parse(&["--seconds", "31536000"]).unwrap().limit,
+            Some(RunLimit::Seconds(MAX_SECONDS))
+        );
+        for value in ["0", "-5", "inf", "NaN", "31622400"] {
+            assert!(
+                parse(&["--seconds", value])
+                    .unwrap_err()
+                    .starts_with(&format!("invalid value for --seconds: {} ", value)),
+                "{}",
+                value
+            );
+        }
+    }
+
+    #[test]
+    fn reads_the_scenario_path() {
+        let options = parse(&["--scenario", "scenarios/rush_hour.toml"]).unwrap();
+        assert_eq!(
+            options.scenario.as_deref(),
+            Some("scenarios/rush_hour.toml")
+        );
+        assert_eq!(
+            parse(&["--scenario"]).unwrap_err(),
+            "missing value for --scenario"
+        );
+    }
+
+    #[test]
+    fn replays_take_their_seed_scenario_and_lanes_from_the_recording() {
+        let options = parse(&["--replay", "session.json", "--headless"]).unwrap();
+        assert_eq!(options.replay.as_deref(), Some("session.json"));
+        for extra in [
+            ["--seed", "3"],
+            ["--scenario", "rush.toml"],
+            ["--lanes", "rs,s,l"],
+        ] {
+            let args = ["--replay", "session.json", extra[0], extra[1]];
+            assert_eq!(
+                parse(&args).unwrap_err(),
+                "--replay takes its seed, scenario and lanes from the replay file"
+            );
+        }
+    }
+
+    #[test]
+    fn only_live_window_sessions_are_recorded() {
+        assert_eq!(parse(&[]).unwrap().record, None);
+        let options = parse(&["--record", "session.json"]).unwrap();
+        assert_eq!(options.record.as_deref(), Some("session.json"));
+        for args in [
+            &["--record", "session.json", "--headless"][..],
+            &["--record", "session.json", "--replay", "old.json"],
+        ] {
+            assert!(
+                parse(args)
+                    .unwrap_err()
+                    .starts_with("--record records window sessions"),
+                "{:?}",
+                args
+            );
+        }
+    }
+
+    #[test]
+    fn reads_the_trajectory_options() {
+        let options = parse(&["--trajectory", "out.csv"]).unwrap();
+        assert_eq!(options.trajectory.as_deref(), Some("out.csv"));
+        assert_eq!(options.trajectory_format, None);
+        assert_eq!(options.sample_every, 1);
+        let options = parse(&["--trajectory-format", "jsonl", "--sample-every", "6"]).unwrap();
+        assert_eq!(options.trajectory_format, Some(TrajectoryFormat::JsonLines));
+        assert_eq!(options.sample_every, 6);
+        assert_eq!(
+            parse(&["--sample-every", "0"]).unwrap_err(),
+            "invalid value for --sample-every: 0"
+        );
+        assert_eq!(
+            parse(&["--trajectory-format", "xml"]).unwrap_err(),
+            "unknown trajectory format: xml (csv|jsonl)"
+        );
+    }
+
+    #[test]
+    fn reads_the_export_paths() {
+        assert_eq!(parse(&[]).unwrap().stats_out, None);
+        let options = parse(&["--stats-out", "runs.csv"]).unwrap();
+        assert_eq!(options.stats_out.as_deref(), Some("runs.csv"));
+        let options = parse(&["--journeys-out", "trips.jsonl"]).unwrap();
+        assert_eq!(options.journeys_out.as_deref(), Some("trips.jsonl"));
+        let options = parse(&["--series-out", "series.csv"]).unwrap();
+        assert_eq!(options.series_out.as_deref(), Some("series.csv"));
+    }
+
+    #[test]
+    fn reads_the_event_levels() {
+        let options = parse(&[]).unwrap();
+        assert_eq!(options.log_level, Some(Level::Warn));
+        assert_eq!(options.events_level, Level::Debug);
+        let options = parse(&[
+            "--log-level",
+            "off",
+            "--events",
+            "-",
+            "--events-level",
+            "trace",
+        ]);
+        let options = options.unwrap();
+        assert_eq!(options.log_level, None);
+        assert_eq!(options.events.as_deref(), Some("-"));
+        assert_eq!(options.events_level, Level::Trace);
+        assert_eq!(
+            parse(&["--log-level", "loud"]).unwrap_err(),
+            "invalid value for --log-level: loud (expected one of trace, debug, info, warn)"
+        );
+    }
+
+    #[test]
+    fn reads_the_close_call_thresholds() {
+        let options = parse(&["--ttc", "2", "--pet", "0.5"]).unwrap();
+        assert_eq!(options.ttc, Some(2.0));
+        assert_eq!(options.pet, Some(0.5));
+        for value in ["0", "-1", "inf", "soon"] {
+            assert_eq!(
+                parse(&["--ttc", value]).unwrap_err(),
+                format!("invalid value for --ttc: {}", value)
+            );
+        }
+    }
+
+    #[test]
+    fn reads_the_signal_timings_and_checks_them_against_the_policy() {
+        let options = parse(&[
+            "--green",
+            "8",
+            "--left-green",
+            "3",
+            "--yellow",
+            "4",
+            "--all-red",
+            "0",
+            "--max-green",
+            "30",
+        ])
+        .unwrap();
+        assert_eq!(
+            options.signals,
+            SignalTimings {
+                green: Some(8.0),
+                left_green: Some(3.0),
+                yellow: Some(4.0),
+                all_red: Some(0.0),
+                max_green: Some(30.0),
+            }
+        );
+        assert_eq!(parse(&[]).unwrap().signals, SignalTimings::default());
+        assert_eq!(
+            parse(&["--yellow", "0"]).unwrap_err(),
+            "invalid value for --yellow: 0"
+        );
+        assert_eq!(
+            parse(&["--all-red", "-1"]).unwrap_err(),
+            "invalid value for --all-red: -1"
+        );
+        assert!(
+            parse(&["--policy", "actuated", "--green", "25"])
+                .unwrap_err()
+                .contains("maximum green 20 s is shorter than the minimum green 25 s")
+        );
+        assert!(parse(&["--policy", "fixed-time", "--green", "25"]).is_ok());
+    }
+
+    #[test]
+    fn reads_the_halt_on_collision_switch() {
+        assert!(parse(&["--halt-on-collision"]).unwrap().halt_on_collision);
+    }
+
+    #[test]
+    fn reads_the_lane_layout() {
+        let options = parse(&["--lanes", "rs,s,l,l"]).unwrap();
+        assert_eq!(options.lanes.unwrap().to_string(), "rs,s,l,l");
+        assert_eq!(parse(&[]).unwrap().lanes, None);
+        assert!(
+            parse(&["--lanes", "l,s,r"])
+                .unwrap_err()
+                .starts_with("invalid value for --lanes: lane l next to lane s would cross")
+        );
+    }
+
+    #[test]
+    fn reads_the_grid_and_refuses_single_intersection_options_with_it() {
+        let grid = parse(&["--grid", "3x2"]).unwrap().grid.unwrap();
+        assert_eq!((grid.columns, grid.rows), (3, 2));
+        assert!(
+            parse(&["--grid", "0x2"])
+                .unwrap_err()
+                .starts_with("invalid value for --grid: ")
+        );
+        for option in [
+            "--scenario",
+            "--replay",
+            "--record",
+            "--journeys-out",
+            "--series-out",
+        ] {
+            assert!(
+                parse(&["--grid", "2x2", option, "file"])
+                    .unwrap_err()
+                    .starts_with("--grid cannot be combined"),
+                "{}",
+                option
+            );
+        }
+    }
+}
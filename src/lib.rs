@@ -0,0 +1,11 @@
+//! Smart Road traffic intersection simulation.
+//!
+//! The `sim` module holds the display-free simulation engine. The SDL2
+//! window, renderer and keyboard input live behind the `gui` feature.
+
+pub mod sim;
+
+#[cfg(feature = "gui")]
+pub mod input;
+#[cfg(feature = "gui")]
+pub mod render;
@@ -1,17 +1,27 @@
-mod input;
-mod render;
-mod traffic;
-mod types;
-mod simulation;
-
-use crate::{
-    input::InputHandler,
-    render::{Statistics, TextureCache, draw_roads},
-    simulation::Simulation,
+mod cli;
+
+use crate::cli::Options;
+use smart_road::sim::{
+    LEVEL_NAMES, LaneLayout, LogSink, POLICY_NAMES, Recording, Replay, RoadNetwork, RunLimit,
+    RunMetadata, Scenario, Simulation, TrajectoryWriter, export_journeys,
+    export_network_statistics, export_series, export_statistics, policy_from_name, run_headless,
+    run_network, run_replay,
 };
-use render::{Sdl2Manager, Vehicle};
-use sdl2::{image::InitFlag, render::TextureCreator};
-use traffic::traffic_manager;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Set when `--trajectory -` streams to stdout, so the human-readable report moves to stderr.
+static REPORT_TO_STDERR: AtomicBool = AtomicBool::new(false);
+
+/// `println!` for the run report, which keeps stdout clear for a streamed trajectory.
+macro_rules! report {
+    ($($arg:tt)*) => {
+        if REPORT_TO_STDERR.load(Ordering::Relaxed) {
+            eprintln!($($arg)*)
+        } else {
+            println!($($arg)*)
+        }
+    };
+}
 
 /*
 lane width = 35px
@@ -20,17 +30,272 @@ car width = 30px
 */
 
 fn main() {
+    let options = Options::parse(std::env::args().skip(1)).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        eprintln!(
+            "usage: smart-road [--headless] [--ticks N | --seconds N] [--seed N] [--policy {}]",
+            POLICY_NAMES.join("|")
+        );
+        eprintln!("                  [--scenario FILE] [--replay FILE] [--record FILE] [--lanes r,s,l]");
+        eprintln!("                  [--grid COLUMNSxROWS]");
+        eprintln!(
+            "                  [--trajectory FILE|- [--trajectory-format csv|jsonl] [--sample-every TICKS]]"
+        );
+        eprintln!("                  [--stats-out FILE.json|FILE.csv] [--journeys-out FILE.csv|FILE.jsonl]");
+        eprintln!("                  [--series-out FILE.csv|FILE.jsonl]");
+        eprintln!(
+            "                  [--log-level {}|off] [--events FILE|- [--events-level LEVEL]]",
+            LEVEL_NAMES.join("|")
+        );
+        eprintln!("                  [--ttc SECONDS] [--pet SECONDS] [--halt-on-collision]");
+        eprintln!(
+            "                  [--green S] [--left-green S] [--yellow S] [--all-red S] [--max-green S]"
+        );
+        std::process::exit(2);
+    });
+
+    let replay = options
+        .replay
+        .as_deref()
+        .map(|path| Replay::new(Recording::load(path).unwrap_or_else(|e| exit_with_error(&e))));
+    let scenario = match &replay {
+        Some(replay) => replay.recording.scenario.clone(),
+        None => options
+            .scenario
+            .as_deref()
+            .map(|path| Scenario::load(path).unwrap_or_else(|e| exit_with_error(&e))),
+    };
+
+    let mut simulation = match (&replay, options.seed) {
+        (Some(replay), _) => Simulation::with_seed(replay.recording.seed),
+        (None, Some(seed)) => Simulation::with_seed(seed),
+        (None, None) => Simulation::new(),
+    };
+    // An explicit --policy wins, so a recorded session can be replayed under another policy
+    let policy = options
+        .policy
+        .as_deref()
+        .or(replay
+            .as_ref()
+            .map(|replay| replay.recording.policy.as_str()))
+        .unwrap_or("occupancy");
+    // Signal timings given on the command line win field by field over the recorded or scenario ones
+    let signals = replay
+        .as_ref()
+        .map(|replay| replay.recording.signals)
+        .or(scenario.as_ref().map(|scenario| scenario.signals))
+        .unwrap_or_default();
+    simulation.signals = options.signals.or(signals);
+    simulation.policy =
+        policy_from_name(policy, &simulation.signals).unwrap_or_else(|e| exit_with_error(&e));
+    // A replay keeps its recorded layout, --lanes is refused with it
+    simulation.geometry.lanes = replay
+        .as_ref()
+        .map(|replay| replay.recording.lanes)
+        .or(options.lanes)
+        .or(scenario.as_ref().and_then(|scenario| scenario.lanes))
+        .unwrap_or(LaneLayout::STANDARD);
+    if let Some(ttc) = options.ttc {
+        simulation.safety.thresholds.ttc = ttc;
+    }
+    if let Some(pet) = options.pet {
+        simulation.safety.thresholds.pet = pet;
+    }
+    simulation.halt_on_collision = options.halt_on_collision;
+    if let Some(level) = options.log_level {
+        simulation.events.subscribe(level, LogSink::stderr());
+    }
+    if let Some(path) = &options.events {
+        let sink = LogSink::create(path).unwrap_or_else(|e| exit_with_error(&e));
+        simulation.events.subscribe(options.events_level, sink);
+    }
+    if options.trajectory.as_deref() == Some("-") {
+        REPORT_TO_STDERR.store(true, Ordering::Relaxed);
+    }
+    report!("Seed: {}", simulation.seed);
+    report!("Policy: {}", simulation.policy.name());
+    report!("Lanes: {}", simulation.geometry.lanes);
+    if let Some(grid) = options.grid {
+        report!("Grid: {}", grid);
+    }
+    if let Some(path) = &options.scenario {
+        report!("Scenario: {}", path);
+    }
+    if let Some(path) = &options.replay {
+        report!("Replay: {}", path);
+    }
+    let scenario_label = options
+        .scenario
+        .clone()
+        .or_else(|| scenario.as_ref().and_then(|scenario| scenario.name.clone()));
+
+    let mut trajectory = options.trajectory.as_deref().map(|path| {
+        TrajectoryWriter::create(path, options.trajectory_format, options.sample_every)
+            .unwrap_or_else(|e| exit_with_error(&e))
+    });
+
+    if options.headless
+        && let Some(grid) = options.grid
+    {
+        let limit = options.limit.unwrap_or(RunLimit::Seconds(60.0));
+        let network = run_network(&mut simulation, grid, limit, trajectory.as_mut())
+            .unwrap_or_else(|e| exit_with_error(&e));
+        if simulation.halted {
+            report!("Halted by a collision at tick {}", simulation.tick);
+        }
+        for line in network.summary_lines() {
+            report!("{}", line);
+        }
+        if let Some(path) = &options.stats_out {
+            save_network_statistics(path, &simulation, &network);
+        }
+        return;
+    }
+
+    if options.headless {
+        let statistics = match replay {
+            Some(replay) => run_replay(&mut simulation, replay, options.limit, trajectory.as_mut()),
+            None => run_headless(
+                &mut simulation,
+                options.limit.unwrap_or(RunLimit::Seconds(60.0)),
+                scenario,
+                trajectory.as_mut(),
+            ),
+        };
+        if simulation.halted {
+            report!("Halted by a collision at tick {}", simulation.tick);
+        }
+        for line in statistics.summary_lines() {
+            report!("{}", line);
+        }
+        report!();
+        for line in statistics.breakdowns.table_lines() {
+            report!("{}", line);
+        }
+        if let Some(path) = &options.stats_out {
+            save_statistics(path, &simulation, scenario_label, &statistics);
+        }
+        if let Some(path) = &options.journeys_out {
+            save_journeys(path, &statistics);
+        }
+        if let Some(path) = &options.series_out {
+            save_series(path, &statistics);
+        }
+        return;
+    }
+
+    run_gui(
+        simulation,
+        scenario,
+        scenario_label,
+        replay,
+        trajectory,
+        &options,
+    );
+}
+
+fn save_statistics(
+    path: &str,
+    simulation: &Simulation,
+    scenario_label: Option<String>,
+    statistics: &smart_road::sim::Statistics,
+) {
+    let metadata = RunMetadata::new(simulation, scenario_label);
+    match export_statistics(path, &metadata, statistics) {
+        Ok(()) => report!("Statistics saved to {}", path),
+        Err(e) => eprintln!("{}", e),
+    }
+}
+
+fn save_network_statistics(path: &str, simulation: &Simulation, network: &RoadNetwork) {
+    let metadata = RunMetadata::new(simulation, None);
+    match export_network_statistics(path, &metadata, network) {
+        Ok(()) => report!("Statistics saved to {}", path),
+        Err(e) => eprintln!("{}", e),
+    }
+}
+
+fn save_journeys(path: &str, statistics: &smart_road::sim::Statistics) {
+    match export_journeys(path, &statistics.journeys) {
+        Ok(()) => report!("Journeys saved to {}", path),
+        Err(e) => eprintln!("{}", e),
+    }
+}
+
+fn save_series(path: &str, statistics: &smart_road::sim::Statistics) {
+    match export_series(path, &statistics.series) {
+        Ok(()) => report!("Series saved to {}", path),
+        Err(e) => eprintln!("{}", e),
+    }
+}
+
+fn exit_with_error(error: &str) -> ! {
+    eprintln!("{}", error);
+    std::process::exit(2);
+}
+
+#[cfg(not(feature = "gui"))]
+fn run_gui(
+    _simulation: Simulation,
+    _scenario: Option<Scenario>,
+    _scenario_label: Option<String>,
+    _replay: Option<Replay>,
+    _trajectory: Option<TrajectoryWriter>,
+    _options: &Options,
+) {
+    eprintln!("smart-road was built without the `gui` feature, use --headless");
+    std::process::exit(2);
+}
+
+/// Longest real frame time (s) fed into the simulation, to avoid catching up after stalls.
+#[cfg(feature = "gui")]
+const MAX_FRAME_TIME: f32 = 0.25;
+
+/// Where `S` saves the statistics when no --stats-out path was given.
+#[cfg(feature = "gui")]
+const DEFAULT_STATS_PATH: &str = "statistics.json";
+
+/// How far one notch of the mouse wheel scrolls a road network (px).
+#[cfg(feature = "gui")]
+const SCROLL_STEP: i32 = 40;
+
+#[cfg(feature = "gui")]
+fn run_gui(
+    mut simulation: Simulation,
+    scenario: Option<Scenario>,
+    scenario_label: Option<String>,
+    replay: Option<Replay>,
+    mut trajectory: Option<TrajectoryWriter>,
+    options: &Options,
+) {
+    use sdl2::{image::InitFlag, render::TextureCreator};
+    use smart_road::{
+        input::InputHandler,
+        render::{
+            FpsCounter, Sdl2Manager, StatsPage, TextureCache, draw_clock_mode, draw_hud,
+            draw_roads, draw_signal_heads,
+        },
+        sim::{SimEvent, Statistics, Vehicle, traffic_manager},
+    };
+    use std::time::Instant;
+
+    // A grid of intersections is scrolled through a window the size of one map
+    let mut network = options
+        .grid
+        .map(|grid| RoadNetwork::new(grid, &simulation).unwrap_or_else(|e| exit_with_error(&e)));
+    let mut view = (0, 0);
+
     sdl2::hint::set("SDL_RENDER_SCALE_QUALITY", "1");
 
-    let mut simulation = Simulation::new();
     let mut vehicles: Vec<Vehicle> = Vec::new();
     let mut completed_vehicles: Vec<Vehicle> = Vec::new();
 
-    // Not fully used yet pending statistics implementation
+    // Passes through the single intersection; a network keeps statistics per intersection
     let mut statistics = Statistics::new();
 
-    // Try to create the SDL2 manager (window size 800x600)
-    let mut sdl2_manager = Sdl2Manager::new("Smart Road", 800, 800)
+    // Try to create the SDL2 manager, the window as large as the map
+    let window_size = simulation.geometry.size as u32;
+    let mut sdl2_manager = Sdl2Manager::new("Smart Road", window_size, window_size)
         .unwrap_or_else(|e| panic!("Failed to initialize SDL2: {}", e));
 
     let ttf_context = sdl2::ttf::init().unwrap();
@@ -39,6 +304,14 @@ fn main() {
         ttf_context.load_font("assets/fonts/Arial.ttf", 24).unwrap();
 
     let mut input = InputHandler::new();
+    if let Some(scenario) = scenario.clone() {
+        input.spawner.load_scenario(scenario);
+    }
+    // With --record the session's inputs are kept so they can be replayed with --replay
+    input.replay = replay;
+    if options.record.is_some() {
+        input.recording = Some(Recording::new(&simulation, scenario));
+    }
 
     let mut event_pump = sdl2_manager
         .sdl_context
@@ -51,13 +324,13 @@ fn main() {
     let texture_cache: TextureCache<'_> = TextureCache::new(&texture_creator);
 
     let mut showing_stats = false;
+    let mut stats_page = StatsPage::Summary;
+
+    let mut last_frame = Instant::now();
+    let mut fps = FpsCounter::new();
 
     'running: loop {
         // Handle events (like closing the window)
-        if !showing_stats {
-            input.reset();
-        }
-
         for event in event_pump.poll_iter() {
             match event {
                 sdl2::event::Event::Quit { .. } => {
@@ -68,38 +341,235 @@ fn main() {
                     //? may need to add repeat flag check here to avoid spawning multiple vehicles on key hold
                     ..
                 } => {
-                    if showing_stats {
-                        // If showing stats, ESC or any key exits
+                    // S saves the statistics so far, on either screen
+                    if keycode == sdl2::keyboard::Keycode::S {
+                        let path = options.stats_out.as_deref().unwrap_or(DEFAULT_STATS_PATH);
+                        match &network {
+                            Some(network) => save_network_statistics(path, &simulation, network),
+                            None => save_statistics(
+                                path,
+                                &simulation,
+                                scenario_label.clone(),
+                                &statistics,
+                            ),
+                        }
+                    } else if showing_stats {
+                        // If showing stats, ESC exits and Tab flips through the pages
                         if keycode == sdl2::keyboard::Keycode::Escape {
                             break 'running;
                         }
+                        if keycode == sdl2::keyboard::Keycode::Tab {
+                            stats_page = stats_page.next();
+                        }
                     } else {
-                        input.handle_keydown(keycode);
+                        input.handle_keydown(keycode, &mut simulation);
                     }
                 }
+                // Dragging with the left button or turning the wheel scrolls a road network
+                sdl2::event::Event::MouseMotion {
+                    mousestate,
+                    xrel,
+                    yrel,
+                    ..
+                } if mousestate.left() => {
+                    view = (view.0 - xrel, view.1 - yrel);
+                }
+                sdl2::event::Event::MouseWheel { x, y, .. } => {
+                    view = (view.0 + x * SCROLL_STEP, view.1 - y * SCROLL_STEP);
+                }
                 _ => {}
             }
         }
+        if let Some(network) = &network {
+            let (width, height) = network.size(&simulation.geometry);
+            let window = simulation.geometry.size;
+            view = (
+                view.0.clamp(0, (width - window) as i32),
+                view.1.clamp(0, (height - window) as i32),
+            );
+        }
 
         if input.quit && !showing_stats {
             showing_stats = true;
         }
 
         if showing_stats {
-            // Render stats and wait for close
-            statistics.render_stats(&mut sdl2_manager, &font);
+            // Render stats and wait for close; for a network, those of the intersection in view
+            let shown = match &network {
+                Some(network) => {
+                    let centre = (view.0 + window_size as i32 / 2) as f32;
+                    let middle = (view.1 + window_size as i32 / 2) as f32;
+                    let index = network.junction_at((centre, middle), &simulation.geometry);
+                    &network.junctions[index].statistics
+                }
+                None => &statistics,
+            };
+            shown.render_stats(&mut sdl2_manager, &font, stats_page, &simulation.geometry);
             sdl2_manager.canvas.present();
             // Prevent high CPU usage while showing stats
             std::thread::sleep(std::time::Duration::from_millis(16));
             continue;
         }
 
-        draw_roads(&mut sdl2_manager, &font, &texture_creator);
+        // The maps in view, each drawn where it sits relative to the view
+        let in_view = |origin: (f32, f32)| {
+            let origin = (origin.0 as i32 - view.0, origin.1 as i32 - view.1);
+            let size = window_size as i32;
+            (origin.0 > -size && origin.0 < size && origin.1 > -size && origin.1 < size)
+                .then_some(origin)
+        };
+        match &network {
+            Some(network) => {
+                for junction in &network.junctions {
+                    if let Some(origin) = in_view(junction.origin(&simulation.geometry)) {
+                        draw_roads(
+                            &mut sdl2_manager,
+                            &font,
+                            &texture_creator,
+                            texture_cache.grass(),
+                            &simulation.geometry,
+                            origin,
+                        );
+                    }
+                }
+            }
+            None => draw_roads(
+                &mut sdl2_manager,
+                &font,
+                &texture_creator,
+                texture_cache.grass(),
+                &simulation.geometry,
+                (0, 0),
+            ),
+        }
 
-        traffic_manager(&mut input, &mut vehicles, &mut statistics, &mut completed_vehicles, &mut simulation);
+        // Run as many fixed steps as the elapsed real time covers, so the physics
+        // rate does not depend on the render rate. Long stalls are clamped.
+        let now = Instant::now();
+        let elapsed = now
+            .duration_since(last_frame)
+            .as_secs_f32()
+            .min(MAX_FRAME_TIME);
+        last_frame = now;
 
-        Vehicle::render(&vehicles, &texture_cache, &mut sdl2_manager);
+        for _ in 0..simulation.steps_for_frame(elapsed) {
+            if let Some(replay) = &mut input.replay {
+                if replay.is_finished(simulation.tick) {
+                    // Hand control back to the keyboard once the recording runs out
+                    simulation.emit(SimEvent::ReplayFinished);
+                    input.replay = None;
+                    simulation.paused = true;
+                    break;
+                }
+                replay.apply_due(simulation.tick, &mut input.spawner);
+            }
+            match &mut network {
+                Some(network) => network.step(&mut input.spawner, &mut simulation),
+                None => traffic_manager(
+                    &mut input.spawner,
+                    &mut vehicles,
+                    &mut statistics,
+                    &mut completed_vehicles,
+                    &mut simulation,
+                ),
+            }
+            // Spawn requests are consumed by the first step that sees them
+            input.reset();
+
+            if let Some(writer) = &mut trajectory {
+                let recorded = match &network {
+                    Some(network) => {
+                        writer.record(&simulation, &network.placed_vehicles(&simulation.geometry))
+                    }
+                    None => writer.record(&simulation, &vehicles),
+                };
+                if let Err(e) = recorded {
+                    eprintln!("{}", e);
+                    trajectory = None;
+                }
+            }
+        }
+
+        match &network {
+            Some(network) => {
+                // Vehicles last, as those just handed off still overlap the previous map
+                for junction in &network.junctions {
+                    if let Some(origin) = in_view(junction.origin(&simulation.geometry)) {
+                        draw_signal_heads(
+                            &mut sdl2_manager,
+                            junction.policy.as_ref(),
+                            &simulation.geometry,
+                            origin,
+                        );
+                    }
+                }
+                for junction in &network.junctions {
+                    if let Some(origin) = in_view(junction.origin(&simulation.geometry)) {
+                        Vehicle::render(
+                            &junction.vehicles,
+                            &texture_cache,
+                            &mut sdl2_manager,
+                            origin,
+                        );
+                    }
+                }
+            }
+            None => {
+                draw_signal_heads(
+                    &mut sdl2_manager,
+                    simulation.policy.as_ref(),
+                    &simulation.geometry,
+                    (0, 0),
+                );
+                Vehicle::render(&vehicles, &texture_cache, &mut sdl2_manager, (0, 0));
+            }
+        }
+
+        draw_clock_mode(&mut sdl2_manager, &font, &texture_creator, &simulation);
+        if input.show_hud {
+            let live = match &network {
+                Some(network) => network.live(simulation.time()),
+                None => statistics.live(&vehicles, simulation.time()),
+            };
+            draw_hud(
+                &mut sdl2_manager,
+                &font,
+                &texture_creator,
+                &simulation,
+                &live,
+                fps.fps(),
+            );
+        }
 
         sdl2_manager.canvas.present();
+        fps.frame();
+    }
+
+    if let Some(writer) = &mut trajectory
+        && let Err(e) = writer.flush()
+    {
+        eprintln!("{}", e);
+    }
+    simulation.events.flush();
+
+    if let Some(path) = &options.stats_out {
+        match &network {
+            Some(network) => save_network_statistics(path, &simulation, network),
+            None => save_statistics(path, &simulation, scenario_label, &statistics),
+        }
+    }
+    if let Some(path) = &options.journeys_out {
+        save_journeys(path, &statistics);
+    }
+    if let Some(path) = &options.series_out {
+        save_series(path, &statistics);
+    }
+
+    if let (Some(mut recording), Some(path)) = (input.recording, &options.record) {
+        recording.ticks = simulation.tick;
+        match recording.save(path) {
+            Ok(()) => report!("Replay saved to {}", path),
+            Err(e) => eprintln!("{}", e),
+        }
     }
 }
@@ -0,0 +1,125 @@
+use crate::render::Sdl2Manager;
+use crate::sim::{LiveStats, Simulation};
+use sdl2::{pixels::Color, rect::Rect, render::TextureCreator, ttf::Font, video::WindowContext};
+use std::time::Instant;
+
+/// Frames rendered per real second, averaged over half a second.
+pub struct FpsCounter {
+    frames: u32,
+    since: Instant,
+    fps: f32,
+}
+
+impl Default for FpsCounter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FpsCounter {
+    pub fn new() -> Self {
+        FpsCounter {
+            frames: 0,
+            since: Instant::now(),
+            fps: 0.0,
+        }
+    }
+
+    /// Counts a rendered frame.
+    pub fn frame(&mut self) {
+        self.frames += 1;
+        let elapsed = self.since.elapsed().as_secs_f32();
+        if elapsed >= 0.5 {
+            self.fps = self.frames as f32 / elapsed;
+            self.frames = 0;
+            self.since = Instant::now();
+        }
+    }
+
+    pub fn fps(&self) -> f32 {
+        self.fps
+    }
+}
+
+/// Draws the clock mode (running speed or paused) in the top-left corner.
+pub fn draw_clock_mode(
+    sdl2_manager: &mut Sdl2Manager,
+    font: &Font<'_, '_>,
+    texture_creator: &TextureCreator<WindowContext>,
+    simulation: &Simulation,
+) {
+    let text = format!("{}  t={:.1}s", simulation.clock_mode(), simulation.time());
+    draw_panel(sdl2_manager, font, texture_creator, &[text], 5);
+}
+
+/**
+ * Draws the live overlay below the clock mode: vehicles on screen and
+ * completed, throughput over the last simulated minute, queues per
+ * approach, close calls, FPS and simulation time.
+ *
+ * # Arguments
+ *
+ * * `stats` - Snapshot from `Statistics::live`.
+ * * `fps` - Frames per real second.
+ */
+pub fn draw_hud(
+    sdl2_manager: &mut Sdl2Manager,
+    font: &Font<'_, '_>,
+    texture_creator: &TextureCreator<WindowContext>,
+    simulation: &Simulation,
+    stats: &LiveStats,
+    fps: f32,
+) {
+    // "Queues N/E/S/W: 3/0/5/1", short enough to stay on the grass
+    let (approaches, queues): (Vec<String>, Vec<String>) = stats
+        .queues
+        .iter()
+        .map(|(from, queue)| (format!("{:?}", from)[..1].to_string(), queue.to_string()))
+        .unzip();
+    let lines = [
+        format!("On screen: {}", stats.on_screen),
+        format!("Completed: {}", stats.completed),
+        format!("Last minute: {}", stats.last_minute),
+        format!("Queues {}: {}", approaches.join("/"), queues.join("/")),
+        format!("Close calls: {}", stats.close_calls),
+        format!("FPS: {:.0}", fps),
+        format!("Sim time: {:.1}s", simulation.time()),
+    ];
+    draw_panel(sdl2_manager, font, texture_creator, &lines, 50);
+}
+
+/// Draws `lines` of white text on a black box whose top edge is at `top`.
+fn draw_panel(
+    sdl2_manager: &mut Sdl2Manager,
+    font: &Font<'_, '_>,
+    texture_creator: &TextureCreator<WindowContext>,
+    lines: &[String],
+    top: i32,
+) {
+    let surfaces: Vec<_> = lines
+        .iter()
+        .map(|line| font.render(line).blended(Color::WHITE).unwrap())
+        .collect();
+    let width = surfaces.iter().map(|s| s.width()).max().unwrap_or(0);
+    let height: u32 = surfaces.iter().map(|s| s.height()).sum();
+
+    // Backing box so the text stays readable over grass and road
+    sdl2_manager.canvas.set_draw_color(Color::RGB(0, 0, 0));
+    sdl2_manager
+        .canvas
+        .fill_rect(Rect::new(5, top, width + 10, height + 10))
+        .unwrap();
+
+    let mut y = top + 5;
+    for surface in surfaces {
+        let texture = texture_creator
+            .create_texture_from_surface(&surface)
+            .unwrap();
+        let target = Rect::new(10, y, surface.width(), surface.height());
+        sdl2_manager
+            .canvas
+            .copy(&texture, None, Some(target))
+            .unwrap();
+        y += surface.height() as i32;
+    }
+}
@@ -1,38 +1,61 @@
 use crate::render::Sdl2Manager;
+use crate::sim::types::Direction;
+use crate::sim::{IntersectionGeometry, IntersectionPolicy, Lane, Signal, Turn};
 use sdl2::{
     pixels::Color,
     rect::{Point, Rect},
-    render::TextureCreator,
+    render::{Texture, TextureCreator},
     ttf::Font,
     video::WindowContext,
 };
 
+/// Draws the map of one intersection, roads, grass and markings, with its
+/// top-left corner at `origin` on screen; the corners are plain green without `grass`.
 pub fn draw_roads(
     sdl2_manager: &mut Sdl2Manager,
     font: &Font<'_, '_>,
     texture_creator: &TextureCreator<WindowContext>,
+    grass: Option<&Texture<'_>>,
+    geometry: &IntersectionGeometry,
+    origin: (i32, i32),
 ) {
-    // Set the draw color to #b0c4de for the road
-    sdl2_manager.canvas.set_draw_color(Color::RGB(176, 196, 222));
-    sdl2_manager.canvas.clear();
+    let (left, top) = origin;
+    let size = geometry.size as i32;
 
-    // Load grass image for corner areas
-    let grass_surface = <sdl2::surface::Surface<'_> as sdl2::image::LoadSurface>::from_file("grass.jpg").ok();
-    let grass_texture = grass_surface
-        .as_ref()
-        .and_then(|surface| texture_creator.create_texture_from_surface(surface).ok());
+    // Set the draw color to #b0c4de for the road
+    sdl2_manager
+        .canvas
+        .set_draw_color(Color::RGB(176, 196, 222));
+    sdl2_manager
+        .canvas
+        .fill_rect(Rect::new(left, top, size as u32, size as u32))
+        .unwrap();
 
-    let top_left = Rect::new(0, 0, 295, 295);
-    let top_right = Rect::new(505, 0, 295, 295);
-    let bottom_left = Rect::new(0, 505, 295, 295);
-    let bottom_right = Rect::new(505, 505, 295, 295);
+    let (low, high) = (geometry.box_min() as i32, geometry.box_max() as i32);
+    let (near, far) = (low as u32, (size - high) as u32);
+    let top_left = Rect::new(left, top, near, near);
+    let top_right = Rect::new(left + high, top, far, near);
+    let bottom_left = Rect::new(left, top + high, near, far);
+    let bottom_right = Rect::new(left + high, top + high, far, far);
 
     // draw corner rects
-    if let Some(grass_texture) = grass_texture {
-        sdl2_manager.canvas.copy(&grass_texture, None, Some(top_left)).unwrap();
-        sdl2_manager.canvas.copy(&grass_texture, None, Some(top_right)).unwrap();
-        sdl2_manager.canvas.copy(&grass_texture, None, Some(bottom_left)).unwrap();
-        sdl2_manager.canvas.copy(&grass_texture, None, Some(bottom_right)).unwrap();
+    if let Some(grass_texture) = grass {
+        sdl2_manager
+            .canvas
+            .copy(grass_texture, None, Some(top_left))
+            .unwrap();
+        sdl2_manager
+            .canvas
+            .copy(grass_texture, None, Some(top_right))
+            .unwrap();
+        sdl2_manager
+            .canvas
+            .copy(grass_texture, None, Some(bottom_left))
+            .unwrap();
+        sdl2_manager
+            .canvas
+            .copy(grass_texture, None, Some(bottom_right))
+            .unwrap();
     } else {
         sdl2_manager.canvas.set_draw_color(Color::RGB(34, 139, 34));
         sdl2_manager.canvas.fill_rect(top_left).unwrap();
@@ -43,14 +66,14 @@ pub fn draw_roads(
 
     //* Font and text */
     let text_surface = font.render("Smart Road").blended(Color::BLACK).unwrap();
-    // let texture_creator = sdl2_manager.canvas.texture_creator();
     let text_texture = texture_creator
         .create_texture_from_surface(&text_surface)
         .unwrap();
 
+    let (centre_x, centre_y) = geometry.centre();
     let target = Rect::new(
-        800 / 2 - (text_surface.width() as i32) / 2,
-        400 - (text_surface.height() as i32) / 2,
+        left + centre_x as i32 - (text_surface.width() as i32) / 2,
+        top + centre_y as i32 - (text_surface.height() as i32) / 2,
         text_surface.width(),
         text_surface.height(),
     );
@@ -59,285 +82,150 @@ pub fn draw_roads(
         .copy(&text_texture, None, Some(target))
         .unwrap();
 
-    draw_lanes(sdl2_manager, font, texture_creator);
+    draw_lanes(sdl2_manager, font, texture_creator, geometry, origin);
 }
 
+/**
+ * Draws the lane separators and stop lines of the four arms, and marks each
+ * inbound lane with the movements it serves (r, s, l) next to its stop line.
+ */
 pub fn draw_lanes(
     sdl2_manager: &mut Sdl2Manager,
     font: &Font<'_, '_>,
     texture_creator: &TextureCreator<WindowContext>,
+    geometry: &IntersectionGeometry,
+    origin: (i32, i32),
 ) {
+    let (left, top) = origin;
+    let (low, high) = (geometry.box_min() as i32, geometry.box_max() as i32);
+    let size = geometry.size as i32;
+    let centre = size / 2;
+    let (near, far) = (low as u32, (size - high) as u32);
+
     // Set separator line color to black
     sdl2_manager.canvas.set_draw_color(Color::RGB(0, 0, 0));
 
-    // Draw thick separator lines between lane sections (l, s, r)
+    // Thick separator lines between neighbouring lanes, inbound and outbound alike
     let separator_width = 2;
-    let top_separators = [
-        Rect::new(330, 0, separator_width, 295), // r | s
-        Rect::new(365, 0, separator_width, 295), // s | l
-        Rect::new(400, 0, separator_width, 295), // l | l
-        Rect::new(435, 0, separator_width, 295), // l | s
-        Rect::new(470, 0, separator_width, 295), // s | r
-    ];
-    for sep in &top_separators {
-        sdl2_manager.canvas.fill_rect(*sep).unwrap();
+    for k in 1..2 * geometry.lanes_per_approach() {
+        let offset = (geometry.box_min() + geometry.lane_width * k as f32) as i32;
+        let separators = [
+            Rect::new(left + offset, top, separator_width, near),
+            Rect::new(left + offset, top + high, separator_width, far),
+            Rect::new(left, top + offset, near, separator_width),
+            Rect::new(left + high, top + offset, far, separator_width),
+        ];
+        for sep in &separators {
+            sdl2_manager.canvas.fill_rect(*sep).unwrap();
+        }
     }
 
-    //* write "r" on the road to indicate stop line
-    let r_surface = font.render("r").blended(Color::BLACK).unwrap();
-    let r_texture = texture_creator
-        .create_texture_from_surface(&r_surface)
-        .unwrap();
-
-    let r_target = Rect::new(305, 270, r_surface.width(), r_surface.height());
-
-    sdl2_manager
-        .canvas
-        .copy(&r_texture, None, Some(r_target))
-        .unwrap();
-
-    //* end of "r" drawing
-
-    sdl2_manager
-        .canvas
-        .draw_line(Point::new(365, 0), Point::new(365, 295))
-        .unwrap();
-
-    //* write "s" on the road to indicate stop line
-    let s_surface = font.render("s").blended(Color::BLACK).unwrap();
-    let s_texture = texture_creator
-        .create_texture_from_surface(&s_surface)
-        .unwrap();
-
-    let s_target = Rect::new(340, 270, s_surface.width(), s_surface.height());
-    sdl2_manager
-        .canvas
-        .copy(&s_texture, None, Some(s_target))
-        .unwrap();
-
-    //* end of "s" drawing
-
-    sdl2_manager
-        .canvas
-        .draw_line(Point::new(400, 0), Point::new(400, 295))
-        .unwrap();
-
-    //* write "l" on the road to indicate stop line
-    let l_surface = font.render("l").blended(Color::BLACK).unwrap();
-    let l_texture = texture_creator
-        .create_texture_from_surface(&l_surface)
-        .unwrap();
-    let l_target = Rect::new(380, 270, l_surface.width(), l_surface.height());
-
-    sdl2_manager
-        .canvas
-        .copy(&l_texture, None, Some(l_target))
-        .unwrap();
-    //* end of "l" drawing
-
-    sdl2_manager
-        .canvas
-        .draw_line(Point::new(435, 0), Point::new(435, 295))
-        .unwrap();
-    sdl2_manager
-        .canvas
-        .draw_line(Point::new(470, 0), Point::new(470, 295))
-        .unwrap();
-    sdl2_manager
-        .canvas
-        .draw_line(Point::new(295, 295), Point::new(400, 295)) // stop
-        .unwrap();
-
-    // bottom
-    let bottom_separators = [
-        Rect::new(330, 505, separator_width, 295),
-        Rect::new(365, 505, separator_width, 295),
-        Rect::new(400, 505, separator_width, 295),
-        Rect::new(435, 505, separator_width, 295),
-        Rect::new(470, 505, separator_width, 295),
+    // Stop lines across the inbound half of each arm, on the edge of the box
+    let stop_lines = [
+        (Point::new(low, low), Point::new(centre, low)),
+        (Point::new(centre, high), Point::new(high, high)),
+        (Point::new(low, centre), Point::new(low, high)),
+        (Point::new(high, low), Point::new(high, centre)),
     ];
-    for sep in &bottom_separators {
-        sdl2_manager.canvas.fill_rect(*sep).unwrap();
+    for (start, end) in stop_lines {
+        sdl2_manager
+            .canvas
+            .draw_line(start.offset(left, top), end.offset(left, top))
+            .unwrap();
     }
 
-    //* write "r" on the road to indicate stop line
-    let r_surface = font.render("r").blended(Color::BLACK).unwrap();
-    let r_texture = texture_creator
-        .create_texture_from_surface(&r_surface)
-        .unwrap();
-    let r_target = Rect::new(485, 505, r_surface.width(), r_surface.height());
-    sdl2_manager
-        .canvas
-        .copy(&r_texture, None, Some(r_target))
-        .unwrap();
-
-    //* write "s" on the road to indicate stop line
-    let s_surface = font.render("s").blended(Color::BLACK).unwrap();
-    let s_texture = texture_creator
-        .create_texture_from_surface(&s_surface)
-        .unwrap();
-         
-    let s_target = Rect::new(448, 505, s_surface.width(), s_surface.height());
-    sdl2_manager
-        .canvas
-        .copy(&s_texture, None, Some(s_target))
-        .unwrap();
-
-    //* end of "s" drawing
-
-    sdl2_manager
-        .canvas
-        .draw_line(Point::new(470, 505), Point::new(470, 800))
-        .unwrap();
-
-    //* write "l" on the road to indicate stop line
-    let l_surface = font.render("l").blended(Color::BLACK).unwrap();
-    let l_texture = texture_creator
-        .create_texture_from_surface(&l_surface)
-        .unwrap();
-    let l_target = Rect::new(415, 505, l_surface.width(), l_surface.height());
-
-    sdl2_manager
-        .canvas
-        .copy(&l_texture, None, Some(l_target))
-        .unwrap();
-    //* end of "l" drawing
-
-    sdl2_manager
-        .canvas
-        .draw_line(Point::new(400, 505), Point::new(505, 505)) // stop
-        .unwrap();
-
-    // left - thick separator lines between lanes
-    let left_separators = [
-        Rect::new(0, 330, 295, separator_width),
-        Rect::new(0, 365, 295, separator_width),
-        Rect::new(0, 400, 295, separator_width),
-        Rect::new(0, 435, 295, separator_width),
-        Rect::new(0, 470, 295, separator_width),
-    ];
-    for sep in &left_separators {
-        sdl2_manager.canvas.fill_rect(*sep).unwrap();
+    for from in [
+        Direction::North,
+        Direction::South,
+        Direction::West,
+        Direction::East,
+    ] {
+        for (slot, lane_use) in geometry.lanes.lanes().iter().enumerate() {
+            let label = lane_use.to_string();
+            let surface = font.render(&label).blended(Color::BLACK).unwrap();
+            let texture = texture_creator
+                .create_texture_from_surface(&surface)
+                .unwrap();
+
+            // Centred in the lane just before the stop line, read facing the traffic
+            let across = geometry.lane_centre(from, slot) as i32;
+            let ((x, y), angle) = match from {
+                Direction::North => ((across, low - 15), 0.0),
+                Direction::South => ((across, high + 15), 0.0),
+                Direction::West => ((low - 15, across), 90.0),
+                Direction::East => ((high + 15, across), 270.0),
+            };
+            let (width, height) = (surface.width(), surface.height());
+            let target = Rect::new(
+                left + x - width as i32 / 2,
+                top + y - height as i32 / 2,
+                width,
+                height,
+            );
+            sdl2_manager
+                .canvas
+                .copy_ex(&texture, None, Some(target), angle, None, false, false)
+                .unwrap();
+        }
     }
+}
 
-    //* write "l" on the road to indicate stop line
-    let l_surface = font.render("l").blended(Color::BLACK).unwrap();
-    let l_texture = texture_creator
-        .create_texture_from_surface(&l_surface)
-        .unwrap();
-    let l_target = Rect::new(280, 403, l_surface.width(), l_surface.height());
-    sdl2_manager
-        .canvas
-        .copy_ex(&l_texture, None, Some(l_target), 90.0, None, false, false)
-        .unwrap();
-    //* end of "l" drawing
-
-    sdl2_manager
-        .canvas
-        .draw_line(Point::new(0, 470), Point::new(295, 470))
-        .unwrap();
-
-    //* write "s" on the road to indicate stop line
-    let s_surface = font.render("s").blended(Color::BLACK).unwrap();
-    let s_texture = texture_creator
-        .create_texture_from_surface(&s_surface)
-        .unwrap();
-    let s_target = Rect::new(275, 438, s_surface.width(), s_surface.height());
-
-    sdl2_manager
-        .canvas
-        .copy_ex(&s_texture, None, Some(s_target), 90.0, None, false, false)
-        .unwrap();
-    //* end of "s" drawing
-
-    //* write "r" on the road to indicate stop line
-    let r_surface = font.render("r").blended(Color::BLACK).unwrap();
-    let r_texture = texture_creator
-        .create_texture_from_surface(&r_surface)
-        .unwrap();
-    let r_target = Rect::new(275, 473, r_surface.width(), r_surface.height());
-
-    sdl2_manager
-        .canvas
-        .copy_ex(&r_texture, None, Some(r_target), 90.0, None, false, false)
-        .unwrap();
-    //* end of "r" drawing
-
-    sdl2_manager
-        .canvas
-        .draw_line(Point::new(295, 400), Point::new(295, 505)) // stop
-        .unwrap();
-
-    // right - thick separator lines between lanes
-    let right_separators = [
-        Rect::new(505, 330, 295, separator_width),
-        Rect::new(505, 365, 295, separator_width),
-        Rect::new(505, 400, 295, separator_width),
-        Rect::new(505, 435, 295, separator_width),
-        Rect::new(505, 470, 295, separator_width),
-    ];
-    for sep in &right_separators {
-        sdl2_manager.canvas.fill_rect(*sep).unwrap();
+/// Draws a signal head for every movement the policy shows a signal to, side
+/// by side for a shared lane, just inside the stop lines drawn by `draw_lanes`.
+pub fn draw_signal_heads(
+    sdl2_manager: &mut Sdl2Manager,
+    policy: &dyn IntersectionPolicy,
+    geometry: &IntersectionGeometry,
+    origin: (i32, i32),
+) {
+    for (slot, lane_use) in geometry.lanes.lanes().iter().enumerate() {
+        let turns: Vec<Turn> = lane_use.turns().collect();
+        for lane in Lane::ALL {
+            let Some(index) = turns.iter().position(|&turn| turn == lane.turn()) else {
+                continue;
+            };
+            let Some(signal) = policy.signal_for(&lane) else {
+                continue;
+            };
+            let color = match signal {
+                Signal::Green => Color::RGB(0, 200, 0),
+                Signal::Yellow => Color::RGB(255, 200, 0),
+                Signal::Red => Color::RGB(220, 0, 0),
+            };
+
+            // Heads of a shared lane are spread across it, centred on the lane
+            let shift = (2 * index as i32 - turns.len() as i32 + 1) * SIGNAL_HEAD_STEP / 2;
+            let mut head = signal_head_rect(geometry, &lane, slot, shift);
+            head.offset(origin.0, origin.1);
+            sdl2_manager.canvas.set_draw_color(Color::RGB(0, 0, 0));
+            sdl2_manager
+                .canvas
+                .fill_rect(Rect::new(
+                    head.x() - 2,
+                    head.y() - 2,
+                    head.width() + 4,
+                    head.height() + 4,
+                ))
+                .unwrap();
+            sdl2_manager.canvas.set_draw_color(color);
+            sdl2_manager.canvas.fill_rect(head).unwrap();
+        }
     }
+}
 
-    //* write "r" on the road to indicate stop line
-    let r_surface = font.render("r").blended(Color::BLACK).unwrap();
-    let r_texture = texture_creator
-        .create_texture_from_surface(&r_surface)
-        .unwrap();
-    let r_target = Rect::new(510, 300, r_surface.width(), r_surface.height());
-
-    sdl2_manager
-        .canvas
-        .copy_ex(&r_texture, None, Some(r_target), 270.0, None, false, false)
-        .unwrap();
-    //* end of "r" drawing
-
-    sdl2_manager
-        .canvas
-        .draw_line(Point::new(505, 365), Point::new(800, 365))
-        .unwrap();
-
-    //* write "s" on the road to indicate stop line
-    let s_surface = font.render("s").blended(Color::BLACK).unwrap();
-    let s_texture = texture_creator
-        .create_texture_from_surface(&s_surface)
-        .unwrap();
-    let s_target = Rect::new(510, 335, s_surface.width(), s_surface.height());
-    sdl2_manager
-        .canvas
-        .copy_ex(&s_texture, None, Some(s_target), 270.0, None, false, false)
-        .unwrap();
-    //* end of "s" drawing
-
-    sdl2_manager
-        .canvas
-        .draw_line(Point::new(505, 400), Point::new(800, 400))
-        .unwrap();
-
-    //* write "l" on the road to indicate stop line
-    let l_surface = font.render("l").blended(Color::BLACK).unwrap();
-    let l_texture = texture_creator
-        .create_texture_from_surface(&l_surface)
-        .unwrap();
-    let l_target = Rect::new(515, 370, l_surface.width(), l_surface.height());
+/// Side of a signal head, and distance between the heads of a shared lane (px).
+const SIGNAL_HEAD_SIZE: i32 = 8;
+const SIGNAL_HEAD_STEP: i32 = 11;
 
-    sdl2_manager
-        .canvas
-        .copy_ex(&l_texture, None, Some(l_target), 270.0, None, false, false)
-        .unwrap();
-    //* end of "l" drawing
+fn signal_head_rect(geometry: &IntersectionGeometry, lane: &Lane, slot: usize, shift: i32) -> Rect {
+    let (low, high) = (geometry.box_min() as i32, geometry.box_max() as i32);
+    let across = geometry.lane_centre(lane.from, slot) as i32 + shift - SIGNAL_HEAD_SIZE / 2;
+    let size = SIGNAL_HEAD_SIZE as u32;
 
-    sdl2_manager
-        .canvas
-        .draw_line(Point::new(505, 435), Point::new(800, 435))
-        .unwrap(); // stop
-    sdl2_manager
-        .canvas
-        .draw_line(Point::new(505, 470), Point::new(800, 470))
-        .unwrap();
-    sdl2_manager
-        .canvas
-        .draw_line(Point::new(505, 295), Point::new(505, 400)) // stop
-        .unwrap();
+    match lane.from {
+        Direction::North => Rect::new(across, low + 3, size, size),
+        Direction::South => Rect::new(across, high - 3 - SIGNAL_HEAD_SIZE, size, size),
+        Direction::West => Rect::new(low + 3, across, size, size),
+        Direction::East => Rect::new(high - 3 - SIGNAL_HEAD_SIZE, across, size, size),
+    }
 }
@@ -1,14 +1,16 @@
 pub mod sdl2_manager;
 pub use sdl2_manager::Sdl2Manager;
 
-pub mod vehicle;
-pub use vehicle::*;
+mod vehicle;
 
 pub mod textures;
 pub use textures::*;
 
-pub mod statistics;
-pub use statistics::*;
+mod statistics;
+pub use statistics::StatsPage;
 
 pub mod intersection;
 pub use intersection::*;
+
+pub mod hud;
+pub use hud::*;
@@ -1,104 +1,513 @@
 use crate::render::sdl2_manager::Sdl2Manager;
-use crate::render::vehicle::Vehicle;
-
-pub struct Statistics {
-    pub max_vehicles_passed: usize,
-    pub max_velocity: f32,
-    pub min_velocity: f32,
-    pub max_crossing_time: f32,
-    pub min_crossing_time: f32,
-    pub close_calls: usize,
+use crate::sim::{Histogram, IntersectionGeometry, Sample, Statistics};
+use sdl2::pixels::Color;
+use sdl2::rect::{Point, Rect};
+use sdl2::render::TextureCreator;
+use sdl2::ttf::Font;
+use sdl2::video::WindowContext;
+
+const LINE_HEIGHT: i32 = 30;
+/// Space kept around the pages and between their panels (px).
+const MARGIN: i32 = 10;
+/// Width of the label column left of a table's values (px).
+const LABEL_WIDTH: i32 = 240;
+const HISTOGRAM_BINS: usize = 12;
+/// Colour and values of one line of a chart, one value per sample.
+type ChartLine = (Color, Vec<f32>);
+
+/// Queue line colours for North, East, South and West, the order of `Sample::queues`.
+const APPROACH_COLORS: [Color; 4] = [
+    Color::RGB(230, 90, 80),
+    Color::RGB(240, 200, 70),
+    Color::RGB(80, 200, 120),
+    Color::RGB(80, 160, 230),
+];
+
+/// Pages of the statistics screen, cycled with Tab.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum StatsPage {
+    Summary,
+    Distributions,
+    Movements,
+    Series,
+}
+
+impl StatsPage {
+    pub fn next(self) -> Self {
+        match self {
+            StatsPage::Summary => StatsPage::Distributions,
+            StatsPage::Distributions => StatsPage::Movements,
+            StatsPage::Movements => StatsPage::Series,
+            StatsPage::Series => StatsPage::Summary,
+        }
+    }
+}
+
+/// Where the pages go in the square window, which is as large as the map.
+#[derive(Copy, Clone)]
+struct Layout {
+    size: i32,
+}
+
+impl Layout {
+    fn new(geometry: &IntersectionGeometry) -> Self {
+        Layout {
+            size: geometry.size as i32,
+        }
+    }
+
+    /// Top of the key help line at the bottom of the window.
+    fn footer(&self) -> i32 {
+        self.size - MARGIN - LINE_HEIGHT
+    }
+
+    /// Left edges of `count` value columns spread across the window right of the labels.
+    fn columns(&self, count: usize) -> Vec<i32> {
+        let step = (self.size - MARGIN - LABEL_WIDTH) / count as i32;
+        (0..count as i32).map(|i| LABEL_WIDTH + i * step).collect()
+    }
+
+    /// `count` panels side by side from `top` down to the key help line.
+    fn side_by_side(&self, count: usize, top: i32) -> Vec<Rect> {
+        let width = (self.size - MARGIN) / count as i32 - MARGIN;
+        let height = self.footer() - MARGIN - top;
+        (0..count as i32)
+            .map(|i| {
+                let x = MARGIN + i * (width + MARGIN);
+                Rect::new(x, top, width.max(1) as u32, height.max(1) as u32)
+            })
+            .collect()
+    }
+
+    /// `count` full width panels stacked above the key help line.
+    fn stacked(&self, count: usize) -> Vec<Rect> {
+        let width = self.size - 2 * MARGIN;
+        let height = (self.footer() - MARGIN) / count as i32 - MARGIN;
+        (0..count as i32)
+            .map(|i| {
+                let y = MARGIN + i * (height + MARGIN);
+                Rect::new(MARGIN, y, width.max(1) as u32, height.max(1) as u32)
+            })
+            .collect()
+    }
 }
 
 impl Statistics {
-    pub fn new() -> Self {
-        Statistics {
-            max_vehicles_passed: 0,
-            max_velocity: 0.0,
-            min_velocity: 0.0,
-            max_crossing_time: 0.0,
-            min_crossing_time: 0.0,
-            close_calls: 0,
+    pub fn render_stats(
+        &self,
+        sdl2_manager: &mut Sdl2Manager,
+        font: &Font,
+        page: StatsPage,
+        geometry: &IntersectionGeometry,
+    ) {
+        sdl2_manager.clear();
+        let texture_creator = sdl2_manager.canvas.texture_creator();
+        let layout = Layout::new(geometry);
+
+        match page {
+            StatsPage::Summary => self.render_summary(sdl2_manager, font, &texture_creator),
+            StatsPage::Distributions => {
+                self.render_distributions(sdl2_manager, font, &texture_creator, layout)
+            }
+            StatsPage::Movements => {
+                self.render_movements(sdl2_manager, font, &texture_creator, layout)
+            }
+            StatsPage::Series => self.render_series(sdl2_manager, font, &texture_creator, layout),
         }
+
+        draw_text(
+            sdl2_manager,
+            font,
+            &texture_creator,
+            "Tab: Next Page   S: Save   ESC: Exit",
+            MARGIN,
+            layout.footer(),
+            Color::WHITE,
+        );
     }
 
-    pub fn calculate_intersection_stats(&mut self, completed_vehicles: &[Vehicle]) {
-        if completed_vehicles.is_empty() {
-            return;
+    fn render_summary(
+        &self,
+        sdl2_manager: &mut Sdl2Manager,
+        font: &Font,
+        texture_creator: &TextureCreator<WindowContext>,
+    ) {
+        let mut stats_lines = self.summary_lines();
+
+        let most_delayed = self.most_delayed(5);
+        if !most_delayed.is_empty() {
+            stats_lines.push(String::new());
+            stats_lines.push("Most delayed:  id  route  wait  in box  delay".to_string());
+            for journey in most_delayed {
+                stats_lines.push(format!(
+                    "#{}  {:?}->{:?}  {:.1}s  {:.1}s  {:.1}s",
+                    journey.id,
+                    journey.lane.from,
+                    journey.lane.to,
+                    journey.waiting_time,
+                    journey.box_time.unwrap_or(0.0),
+                    journey.delay,
+                ));
+            }
         }
 
-        // Max vehicles that passed the intersection
-        self.max_vehicles_passed = completed_vehicles.len();
+        for (i, line) in stats_lines.iter().enumerate() {
+            let y_pos = MARGIN + (i as i32 * LINE_HEIGHT);
+            draw_text(
+                sdl2_manager,
+                font,
+                texture_creator,
+                line,
+                MARGIN,
+                y_pos,
+                Color::WHITE,
+            );
+        }
+    }
 
-        // Calculate velocity stats from all vehicles
-        let max_speeds: Vec<f32> = completed_vehicles
-            .iter()
-            .map(|v| v.max_speed_reached)
-            .collect();
-        let min_speeds: Vec<f32> = completed_vehicles
-            .iter()
-            .map(|v| v.min_speed_reached)
-            .collect();
+    /// A table of mean, percentiles and spread, with a histogram per metric below it.
+    fn render_distributions(
+        &self,
+        sdl2_manager: &mut Sdl2Manager,
+        font: &Font,
+        texture_creator: &TextureCreator<WindowContext>,
+        layout: Layout,
+    ) {
+        let names = ["mean", "p50", "p90", "p99", "std"];
+        let columns: Vec<(&str, i32)> =
+            names.into_iter().zip(layout.columns(names.len())).collect();
+        let grey = Color::RGB(170, 170, 170);
 
-        if !max_speeds.is_empty() {
-            self.max_velocity = max_speeds.iter().fold(0.0f32, |a, &b| a.max(b));
+        for &(name, x) in &columns {
+            draw_text(sdl2_manager, font, texture_creator, name, x, MARGIN, grey);
         }
-        if !min_speeds.is_empty() {
-            self.min_velocity = min_speeds.iter().fold(f32::MAX, |a, &b| a.min(b));
+        let lines = self.distribution_lines();
+        for (row, (label, distribution)) in lines.iter().enumerate() {
+            let y = MARGIN + (row as i32 + 1) * LINE_HEIGHT;
+            draw_text(
+                sdl2_manager,
+                font,
+                texture_creator,
+                label,
+                MARGIN,
+                y,
+                Color::WHITE,
+            );
+            let values = [
+                distribution.mean,
+                distribution.p50,
+                distribution.p90,
+                distribution.p99,
+                distribution.std_dev,
+            ];
+            for ((_, x), value) in columns.iter().zip(values) {
+                let text = format!("{:.1}", value);
+                draw_text(
+                    sdl2_manager,
+                    font,
+                    texture_creator,
+                    &text,
+                    *x,
+                    y,
+                    Color::WHITE,
+                );
+            }
+        }
+
+        let samples = [
+            ("Crossing time (s)", self.crossing_times()),
+            ("Speed (px/s)", self.average_speeds()),
+            ("Waiting time (s)", self.waiting_times()),
+        ];
+        // Below the table, a blank line under its last row
+        let top = MARGIN + (lines.len() as i32 + 2) * LINE_HEIGHT;
+        let areas = layout.side_by_side(samples.len(), top);
+        for ((label, values), area) in samples.iter().zip(areas) {
+            let histogram = Histogram::new(values, HISTOGRAM_BINS);
+            draw_histogram(sdl2_manager, font, texture_creator, label, &histogram, area);
         }
+    }
 
-        // Calculate crossing time stats (only vehicles that completed intersection)
-        let crossing_times: Vec<f32> = completed_vehicles
+    /// Throughput, waiting, delay, queue and close calls per approach and per movement.
+    fn render_movements(
+        &self,
+        sdl2_manager: &mut Sdl2Manager,
+        font: &Font,
+        texture_creator: &TextureCreator<WindowContext>,
+        layout: Layout,
+    ) {
+        let names = ["done", "wait", "delay", "queue", "max", "close"];
+        let columns: Vec<(&str, i32)> =
+            names.into_iter().zip(layout.columns(names.len())).collect();
+        let grey = Color::RGB(170, 170, 170);
+
+        for &(name, x) in &columns {
+            draw_text(sdl2_manager, font, texture_creator, name, x, MARGIN, grey);
+        }
+        for (row, (label, stats)) in self.breakdowns.rows().into_iter().enumerate() {
+            let y = MARGIN + (row as i32 + 1) * LINE_HEIGHT;
+            // Approach totals in white, their movements indented in grey
+            let color = if label.starts_with(' ') {
+                grey
+            } else {
+                Color::WHITE
+            };
+            draw_text(
+                sdl2_manager,
+                font,
+                texture_creator,
+                &label,
+                MARGIN,
+                y,
+                color,
+            );
+            let values = [
+                stats.throughput.to_string(),
+                format!("{:.1}", stats.mean_waiting_time),
+                format!("{:.1}", stats.mean_delay),
+                format!("{:.1}", stats.mean_queue),
+                stats.max_queue.to_string(),
+                stats.close_calls.to_string(),
+            ];
+            for ((_, x), text) in columns.iter().zip(values) {
+                draw_text(sdl2_manager, font, texture_creator, &text, *x, y, color);
+            }
+        }
+    }
+
+    /// Line charts of throughput, mean speed and queue per approach over time.
+    fn render_series(
+        &self,
+        sdl2_manager: &mut Sdl2Manager,
+        font: &Font,
+        texture_creator: &TextureCreator<WindowContext>,
+        layout: Layout,
+    ) {
+        let samples: Vec<_> = self.series.samples().collect();
+        let (start, end) = match (samples.first(), samples.last()) {
+            (Some(first), Some(last)) => (first.time, last.time),
+            _ => (0.0, 0.0),
+        };
+        let line = |value: &dyn Fn(&Sample) -> f32| samples.iter().map(|s| value(s)).collect();
+
+        let throughput = [(Color::RGB(80, 160, 230), line(&|s| s.throughput as f32))];
+        let speed = [(Color::RGB(80, 200, 120), line(&|s| s.mean_speed))];
+        let queues: Vec<ChartLine> = APPROACH_COLORS
             .iter()
-            .filter_map(|v| v.intersection_exit_time)
+            .enumerate()
+            .map(|(i, &color)| (color, line(&|s| s.queues[i].1 as f32)))
             .collect();
 
-        if !crossing_times.is_empty() {
-            self.max_crossing_time = crossing_times.iter().fold(0.0f32, |a, &b| a.max(b));
-            self.min_crossing_time = crossing_times.iter().fold(f32::MAX, |a, &b| a.min(b));
+        let charts: [(&str, &[ChartLine]); 3] = [
+            ("Throughput (vehicles/min)", &throughput),
+            ("Mean speed (px/s)", &speed),
+            ("Queue (vehicles)", &queues),
+        ];
+        let areas = layout.stacked(charts.len());
+        for ((title, lines), area) in charts.into_iter().zip(areas.iter().copied()) {
+            draw_line_chart(
+                sdl2_manager,
+                font,
+                texture_creator,
+                title,
+                lines,
+                (start, end),
+                area,
+            );
+        }
+
+        // Legend for the queue chart, one colour per approach across the right half of its title line
+        let queue_area = areas[2];
+        let half = queue_area.width() as i32 / 2;
+        let spacing = half / APPROACH_COLORS.len() as i32;
+        if let Some(sample) = samples.first() {
+            for (i, ((from, _), color)) in sample.queues.iter().zip(APPROACH_COLORS).enumerate() {
+                let text = format!("{:?}", from);
+                let x = queue_area.x() + half + i as i32 * spacing;
+                draw_text(
+                    sdl2_manager,
+                    font,
+                    texture_creator,
+                    &text,
+                    x,
+                    queue_area.y(),
+                    color,
+                );
+            }
         }
+    }
+}
+
+/**
+ * Draws `lines` as a line chart inside `area`, all sharing one vertical scale
+ * from zero to their peak, with the title and peak above and the time range below.
+ *
+ * # Arguments
+ *
+ * * `lines` - The lines, drawn in order.
+ * * `times` - Simulation times of the first and last sample (s).
+ */
+fn draw_line_chart(
+    sdl2_manager: &mut Sdl2Manager,
+    font: &Font,
+    texture_creator: &TextureCreator<WindowContext>,
+    title: &str,
+    lines: &[ChartLine],
+    times: (f32, f32),
+    area: Rect,
+) {
+    let peak = lines
+        .iter()
+        .flat_map(|(_, values)| values.iter().copied())
+        .fold(0.0f32, f32::max);
+    let heading = format!("{}   peak {:.1}", title, peak);
+    draw_text(
+        sdl2_manager,
+        font,
+        texture_creator,
+        &heading,
+        area.x(),
+        area.y(),
+        Color::WHITE,
+    );
+
+    let plot = Rect::new(
+        area.x(),
+        area.y() + LINE_HEIGHT + 5,
+        area.width(),
+        area.height() - 2 * LINE_HEIGHT as u32 - 10,
+    );
+    sdl2_manager.canvas.set_draw_color(Color::RGB(90, 90, 90));
+    sdl2_manager.canvas.draw_rect(plot).unwrap();
 
-        // Count close calls
-        self.close_calls = completed_vehicles
+    let scale = peak.max(1.0);
+    for (color, values) in lines {
+        let last = values.len().saturating_sub(1).max(1) as f32;
+        let points: Vec<Point> = values
             .iter()
-            .filter(|v| v.had_close_call)
-            .count();
+            .enumerate()
+            .map(|(i, value)| {
+                let x = plot.x() + 1 + (i as f32 / last * (plot.width() - 2) as f32) as i32;
+                let y = plot.bottom() - 1 - (value / scale * (plot.height() - 2) as f32) as i32;
+                Point::new(x, y)
+            })
+            .collect();
+        sdl2_manager.canvas.set_draw_color(*color);
+        sdl2_manager.canvas.draw_lines(points.as_slice()).unwrap();
     }
 
-    pub fn render_stats(&self, sdl2_manager: &mut Sdl2Manager, font: &sdl2::ttf::Font) {
-        sdl2_manager.clear();
+    let grey = Color::RGB(170, 170, 170);
+    let low = format!("{:.0}s", times.0);
+    let high = format!("{:.0}s", times.1);
+    draw_text(
+        sdl2_manager,
+        font,
+        texture_creator,
+        &low,
+        plot.x(),
+        plot.bottom() + 5,
+        grey,
+    );
+    draw_text(
+        sdl2_manager,
+        font,
+        texture_creator,
+        &high,
+        plot.right() - 70,
+        plot.bottom() + 5,
+        grey,
+    );
+}
 
-        let stats_lines = vec![
-            format!(
-                "Max Vehicles Passed the intersection: {}",
-                self.max_vehicles_passed
-            ),
-            format!("Max Velocity: {:.2}", self.max_velocity),
-            format!("Min Velocity: {:.2}", self.min_velocity),
-            format!("Max Crossing Time: {:.2} s", self.max_crossing_time),
-            format!("Min Crossing Time: {:.2} s", self.min_crossing_time),
-            format!("Close Calls: {}", self.close_calls),
-            format!("Press ESC to Exit"),
-        ];
+/// Draws `histogram` as vertical bars inside `area`, with its title above and range below.
+fn draw_histogram(
+    sdl2_manager: &mut Sdl2Manager,
+    font: &Font,
+    texture_creator: &TextureCreator<WindowContext>,
+    title: &str,
+    histogram: &Histogram,
+    area: Rect,
+) {
+    draw_text(
+        sdl2_manager,
+        font,
+        texture_creator,
+        title,
+        area.x(),
+        area.y(),
+        Color::WHITE,
+    );
 
-        let texture_creator = sdl2_manager.canvas.texture_creator();
-        let line_height = 30;
+    let plot = Rect::new(
+        area.x(),
+        area.y() + LINE_HEIGHT + 5,
+        area.width(),
+        area.height() - 2 * LINE_HEIGHT as u32 - 10,
+    );
+    sdl2_manager.canvas.set_draw_color(Color::RGB(90, 90, 90));
+    sdl2_manager.canvas.draw_rect(plot).unwrap();
 
-        for (i, line) in stats_lines.iter().enumerate() {
-            let surface = font
-                .render(line)
-                .blended(sdl2::pixels::Color::WHITE)
-                .unwrap();
-            let texture = texture_creator
-                .create_texture_from_surface(&surface)
-                .unwrap();
-            let y_pos = 10 + (i as i32 * line_height);
-            let target = sdl2::rect::Rect::new(10, y_pos, surface.width(), surface.height());
-
-            sdl2_manager
-                .canvas
-                .copy(&texture, None, Some(target))
-                .unwrap();
+    let highest = histogram.counts.iter().copied().max().unwrap_or(0).max(1);
+    let bar_width = plot.width() / histogram.counts.len() as u32;
+    sdl2_manager.canvas.set_draw_color(Color::RGB(80, 160, 230));
+    for (bin, &count) in histogram.counts.iter().enumerate() {
+        let height = (count as f32 / highest as f32 * (plot.height() - 2) as f32) as u32;
+        if height == 0 {
+            continue;
         }
+        let bar = Rect::new(
+            plot.x() + 1 + (bin as u32 * bar_width) as i32,
+            plot.bottom() - 1 - height as i32,
+            bar_width.saturating_sub(2).max(1),
+            height,
+        );
+        sdl2_manager.canvas.fill_rect(bar).unwrap();
     }
+
+    let grey = Color::RGB(170, 170, 170);
+    let low = format!("{:.0}", histogram.start);
+    let high = format!("{:.0}", histogram.end());
+    draw_text(
+        sdl2_manager,
+        font,
+        texture_creator,
+        &low,
+        plot.x(),
+        plot.bottom() + 5,
+        grey,
+    );
+    draw_text(
+        sdl2_manager,
+        font,
+        texture_creator,
+        &high,
+        plot.right() - 50,
+        plot.bottom() + 5,
+        grey,
+    );
+}
+
+fn draw_text(
+    sdl2_manager: &mut Sdl2Manager,
+    font: &Font,
+    texture_creator: &TextureCreator<WindowContext>,
+    text: &str,
+    x: i32,
+    y: i32,
+    color: Color,
+) {
+    // SDL_ttf cannot render an empty string; blank lines are just spacing
+    if text.is_empty() {
+        return;
+    }
+    let surface = font.render(text).blended(color).unwrap();
+    let texture = texture_creator
+        .create_texture_from_surface(&surface)
+        .unwrap();
+    let target = Rect::new(x, y, surface.width(), surface.height());
+
+    sdl2_manager
+        .canvas
+        .copy(&texture, None, Some(target))
+        .unwrap();
 }
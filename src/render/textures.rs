@@ -1,11 +1,13 @@
-use crate::types::{Direction, VehicleColor};
+use crate::sim::types::VehicleColor;
 use sdl2::{image::LoadTexture, render::Texture};
 use std::collections::HashMap;
 
-type TextureKey = (VehicleColor, Direction);
-
+/// One sprite per color, drawn facing east and rotated to the vehicle's heading,
+/// and the grass of the map corners, all loaded once at startup.
 pub struct TextureCache<'a> {
-    textures: HashMap<TextureKey, Texture<'a>>,
+    textures: HashMap<VehicleColor, Texture<'a>>,
+    /// `None` when `grass.jpg` cannot be loaded; the corners are then drawn plain green.
+    grass: Option<Texture<'a>>,
 }
 
 impl<'a> TextureCache<'a> {
@@ -21,41 +23,35 @@ impl<'a> TextureCache<'a> {
             VehicleColor::Yellow,
         ];
 
-        let directions = [
-            Direction::East,
-            Direction::North,
-            Direction::South,
-            Direction::West,
-        ];
-
         for color in colors {
-            for direction in directions {
-                let direction_str = match direction {
-                    Direction::North => "north",
-                    Direction::South => "south",
-                    Direction::East => "east",
-                    Direction::West => "west",
-                };
-
-                let color_str = match color {
-                    VehicleColor::Blue => "blue",
-                    VehicleColor::Green => "green",
-                    VehicleColor::Pink => "pink",
-                    VehicleColor::Yellow => "yellow",
-                };
-
-                let path = format!("assets/vehicles/{}/car_{}.png", direction_str, color_str);
-
-                let texture = texture_creator
-                    .load_texture(&path)
-                    .expect("Failed to load texture");
-                textures.insert((color, direction), texture);
-            }
+            let color_str = match color {
+                VehicleColor::Blue => "blue",
+                VehicleColor::Green => "green",
+                VehicleColor::Pink => "pink",
+                VehicleColor::Yellow => "yellow",
+            };
+
+            let path = format!("assets/vehicles/east/car_{}.png", color_str);
+
+            let texture = texture_creator
+                .load_texture(&path)
+                .expect("Failed to load texture");
+            textures.insert(color, texture);
         }
-        Self { textures }
+
+        let grass =
+            <sdl2::surface::Surface<'_> as sdl2::image::LoadSurface>::from_file("grass.jpg")
+                .ok()
+                .and_then(|surface| texture_creator.create_texture_from_surface(&surface).ok());
+
+        Self { textures, grass }
+    }
+
+    pub fn get(&self, color: VehicleColor) -> &Texture<'a> {
+        &self.textures[&color]
     }
 
-    pub fn get(&self, color: VehicleColor, direction: Direction) -> &Texture<'a> {
-        &self.textures[&(color, direction)]
+    pub fn grass(&self) -> Option<&Texture<'a>> {
+        self.grass.as_ref()
     }
 }
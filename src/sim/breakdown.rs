@@ -0,0 +1,294 @@
+use crate::sim::types::{Direction, VehicleState};
+use crate::sim::{Collision, Conflict, Journey, Lane, Vehicle};
+use serde::Serialize;
+
+/// Metrics for one group of vehicles, a movement or a whole approach.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct Breakdown {
+    /// Vehicles that completed their journey.
+    pub throughput: usize,
+    pub mean_waiting_time: f32,
+    pub mean_delay: f32,
+    /// Close-call pairs, each counted under the movement of its later-spawned
+    /// vehicle so that the rows add up to the total.
+    pub close_calls: usize,
+    /// Vehicles queued (waiting before the box), averaged over every tick.
+    pub mean_queue: f32,
+    pub max_queue: usize,
+    #[serde(skip)]
+    queue_total: u64,
+}
+
+impl Breakdown {
+    fn add_queue_sample(&mut self, queue: usize, samples: u64) {
+        self.queue_total += queue as u64;
+        self.max_queue = self.max_queue.max(queue);
+        self.mean_queue = self.queue_total as f32 / samples as f32;
+    }
+
+    fn set_journeys<'a>(&mut self, journeys: impl Iterator<Item = &'a Journey>) {
+        let (mut count, mut waiting, mut delay) = (0, 0.0, 0.0);
+        for journey in journeys {
+            count += 1;
+            waiting += journey.waiting_time;
+            delay += journey.delay;
+        }
+        self.throughput = count;
+        if count > 0 {
+            self.mean_waiting_time = waiting / count as f32;
+            self.mean_delay = delay / count as f32;
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct LaneBreakdown {
+    pub lane: Lane,
+    #[serde(flatten)]
+    pub stats: Breakdown,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ApproachBreakdown {
+    pub from: Direction,
+    #[serde(flatten)]
+    pub stats: Breakdown,
+}
+
+/// The same metrics per movement (`Lane`) and per approach `Direction`.
+#[derive(Clone, Debug, Serialize)]
+pub struct Breakdowns {
+    pub by_lane: Vec<LaneBreakdown>,
+    pub by_approach: Vec<ApproachBreakdown>,
+    /// Number of queue samples taken, one per tick.
+    #[serde(skip)]
+    queue_samples: u64,
+}
+
+impl Default for Breakdowns {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Breakdowns {
+    pub fn new() -> Self {
+        Breakdowns {
+            by_lane: Lane::ALL
+                .into_iter()
+                .map(|lane| LaneBreakdown {
+                    lane,
+                    stats: Breakdown::default(),
+                })
+                .collect(),
+            by_approach: [
+                Direction::North,
+                Direction::East,
+                Direction::South,
+                Direction::West,
+            ]
+            .into_iter()
+            .map(|from| ApproachBreakdown {
+                from,
+                stats: Breakdown::default(),
+            })
+            .collect(),
+            queue_samples: 0,
+        }
+    }
+
+    /// Adds the current queue lengths to the running queue metrics.
+    pub fn sample_queues(&mut self, vehicles: &[Vehicle]) {
+        self.queue_samples += 1;
+        let samples = self.queue_samples;
+
+        let queued: Vec<&Vehicle> = vehicles.iter().filter(|v| is_queued(v)).collect();
+        for entry in &mut self.by_lane {
+            let queue = queued.iter().filter(|v| v.lane == entry.lane).count();
+            entry.stats.add_queue_sample(queue, samples);
+        }
+        for entry in &mut self.by_approach {
+            let queue = queued.iter().filter(|v| v.lane.from == entry.from).count();
+            entry.stats.add_queue_sample(queue, samples);
+        }
+    }
+
+    /// Display rows: each approach followed by its movements, with a label for each.
+    pub fn rows(&self) -> Vec<(String, &Breakdown)> {
+        let mut rows = Vec::new();
+        for approach in &self.by_approach {
+            rows.push((format!("From {:?}", approach.from), &approach.stats));
+            for entry in self.by_lane.iter().filter(|e| e.lane.from == approach.from) {
+                let label = format!("  {:?}->{:?}", entry.lane.from, entry.lane.to);
+                rows.push((label, &entry.stats));
+            }
+        }
+        rows
+    }
+
+    /// The rows as a fixed-width text table, headed by the column names.
+    pub fn table_lines(&self) -> Vec<String> {
+        let mut lines = vec![format!(
+            "{:<16}{:>7}{:>9}{:>9}{:>8}{:>6}{:>7}",
+            "Movement", "done", "wait s", "delay s", "queue", "max", "close"
+        )];
+        for (label, stats) in self.rows() {
+            lines.push(format!(
+                "{:<16}{:>7}{:>9.2}{:>9.2}{:>8.2}{:>6}{:>7}",
+                label,
+                stats.throughput,
+                stats.mean_waiting_time,
+                stats.mean_delay,
+                stats.mean_queue,
+                stats.max_queue,
+                stats.close_calls,
+            ));
+        }
+        lines
+    }
+
+    /// Counts a close-call pair under the movement and approach of its later-spawned vehicle.
+    pub fn add_conflict(&mut self, conflict: &Conflict) {
+        let lane = conflict.lanes[1];
+        for entry in self.by_lane.iter_mut().filter(|e| e.lane == lane) {
+            entry.stats.close_calls += 1;
+        }
+        for entry in self.by_approach.iter_mut().filter(|e| e.from == lane.from) {
+            entry.stats.close_calls += 1;
+        }
+    }
+
+    /// Recomputes throughput, waiting and delay from the journeys so far.
+    pub fn update_journeys(&mut self, journeys: &[Journey]) {
+        for entry in &mut self.by_lane {
+            entry
+                .stats
+                .set_journeys(journeys.iter().filter(|j| j.lane == entry.lane));
+        }
+        for entry in &mut self.by_approach {
+            entry
+                .stats
+                .set_journeys(journeys.iter().filter(|j| j.lane.from == entry.from));
+        }
+    }
+}
+
+/// A vehicle held up before the intersection box, at its stop line or behind another vehicle.
+pub fn is_queued(vehicle: &Vehicle) -> bool {
+    vehicle.state == VehicleState::Waiting && !Collision::is_vehicle_in_intersection(vehicle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sim::{IntersectionGeometry, SafetyMeasure};
+    use rand::{SeedableRng, rngs::StdRng};
+
+    fn journey(from: Direction, to: Direction, waiting_time: f32) -> Journey {
+        let mut rng = StdRng::seed_from_u64(0);
+        let lane = Lane::set(from, to);
+        let geometry = IntersectionGeometry::STANDARD;
+        let slot = geometry.lane_slots(&lane)[0];
+        let mut vehicle = Vehicle::new(0, lane, slot, &geometry, 0.0, &mut rng);
+        vehicle.waiting_time = waiting_time;
+        Journey::from_vehicle(&vehicle, 10.0)
+    }
+
+    fn lane(breakdowns: &Breakdowns, from: Direction, to: Direction) -> &Breakdown {
+        let lane = Lane::set(from, to);
+        &breakdowns
+            .by_lane
+            .iter()
+            .find(|e| e.lane == lane)
+            .unwrap()
+            .stats
+    }
+
+    #[test]
+    fn journeys_are_grouped_by_movement_and_approach() {
+        let mut breakdowns = Breakdowns::new();
+        breakdowns.update_journeys(&[
+            journey(Direction::North, Direction::South, 2.0),
+            journey(Direction::North, Direction::South, 4.0),
+            journey(Direction::North, Direction::East, 0.0),
+            journey(Direction::West, Direction::East, 1.0),
+        ]);
+
+        let straight = lane(&breakdowns, Direction::North, Direction::South);
+        assert_eq!(straight.throughput, 2);
+        assert_eq!(straight.mean_waiting_time, 3.0);
+        assert_eq!(breakdowns.by_approach[0].from, Direction::North);
+        assert_eq!(breakdowns.by_approach[0].stats.throughput, 3);
+        assert_eq!(breakdowns.by_approach[0].stats.mean_waiting_time, 2.0);
+        assert_eq!(breakdowns.by_approach[2].stats.throughput, 0);
+
+        // Every approach row is followed by its own movements, which add up to it
+        let rows = breakdowns.rows();
+        assert_eq!(rows.len(), 4 + Lane::ALL.len());
+        for chunk in rows.chunks(4) {
+            let movements: usize = chunk[1..].iter().map(|(_, s)| s.throughput).sum();
+            assert_eq!(chunk[0].1.throughput, movements);
+        }
+        assert_eq!(breakdowns.table_lines().len(), rows.len() + 1);
+    }
+
+    #[test]
+    fn queues_are_averaged_over_every_sample() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let lane_ns = Lane::set(Direction::North, Direction::South);
+        let geometry = IntersectionGeometry::STANDARD;
+        let slot = geometry.lane_slots(&lane_ns)[0];
+        let mut queued = Vehicle::new(0, lane_ns, slot, &geometry, 0.0, &mut rng);
+        queued.state = VehicleState::Waiting;
+        let moving = Vehicle::new(1, lane_ns, slot, &geometry, 0.0, &mut rng);
+        assert!(is_queued(&queued));
+        assert!(!is_queued(&moving));
+
+        let mut breakdowns = Breakdowns::new();
+        breakdowns.sample_queues(&[queued.clone(), moving]);
+        breakdowns.sample_queues(&[queued.clone(), queued]);
+        breakdowns.sample_queues(&[]);
+
+        let stats = lane(&breakdowns, Direction::North, Direction::South);
+        assert_eq!(stats.max_queue, 2);
+        assert_eq!(stats.mean_queue, 1.0);
+        assert_eq!(breakdowns.by_approach[0].stats.mean_queue, 1.0);
+        assert_eq!(breakdowns.by_approach[1].stats.max_queue, 0);
+    }
+
+    #[test]
+    fn a_close_call_pair_counts_under_its_later_vehicle() {
+        let mut breakdowns = Breakdowns::new();
+        breakdowns.add_conflict(&Conflict {
+            time: 1.0,
+            vehicles: [3, 4],
+            lanes: [
+                Lane::set(Direction::North, Direction::South),
+                Lane::set(Direction::West, Direction::East),
+            ],
+            measure: SafetyMeasure::Ttc,
+            value: 0.5,
+        });
+
+        assert_eq!(
+            lane(&breakdowns, Direction::West, Direction::East).close_calls,
+            1
+        );
+        assert_eq!(
+            lane(&breakdowns, Direction::North, Direction::South).close_calls,
+            0
+        );
+        let west = breakdowns
+            .by_approach
+            .iter()
+            .find(|e| e.from == Direction::West)
+            .unwrap();
+        assert_eq!(west.stats.close_calls, 1);
+        let total: usize = breakdowns
+            .by_approach
+            .iter()
+            .map(|e| e.stats.close_calls)
+            .sum();
+        assert_eq!(total, 1);
+    }
+}
@@ -0,0 +1,386 @@
+use crate::sim::types::VehicleState;
+use crate::sim::{Lane, RouteConflicts, VEHICLE_SIZE, Vehicle};
+use serde::Serialize;
+
+/// An oriented bounding box: a rectangle turned by `angle` radians about its centre.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Obb {
+    pub centre: (f32, f32),
+    /// Half the extent along the heading (px).
+    pub half_length: f32,
+    /// Half the extent across the heading (px).
+    pub half_width: f32,
+    pub angle: f32,
+}
+
+impl Obb {
+    /// The footprint of `vehicle`, the size of its sprite, turned to its heading.
+    pub fn of_vehicle(vehicle: &Vehicle) -> Self {
+        Self::square(vehicle.centre(), vehicle.heading)
+    }
+
+    /// A vehicle footprint centred on `centre` and turned by `angle`.
+    pub fn square(centre: (f32, f32), angle: f32) -> Self {
+        Obb {
+            centre,
+            half_length: VEHICLE_SIZE / 2.0,
+            half_width: VEHICLE_SIZE / 2.0,
+            angle,
+        }
+    }
+
+    /// Unit vectors along and across the heading.
+    fn axes(&self) -> [(f32, f32); 2] {
+        let (sin, cos) = self.angle.sin_cos();
+        [(cos, sin), (-sin, cos)]
+    }
+
+    pub fn corners(&self) -> [(f32, f32); 4] {
+        let [(ax, ay), (bx, by)] = self.axes();
+        let (cx, cy) = self.centre;
+        let (l, w) = (self.half_length, self.half_width);
+        [(1.0, 1.0), (1.0, -1.0), (-1.0, -1.0), (-1.0, 1.0)]
+            .map(|(s, t)| (cx + s * l * ax + t * w * bx, cy + s * l * ay + t * w * by))
+    }
+
+    /// Whether the two boxes overlap, by the separating axis test; touching edges do not count.
+    pub fn overlaps(&self, other: &Obb) -> bool {
+        let (mine, theirs) = (self.corners(), other.corners());
+        self.axes().into_iter().chain(other.axes()).all(|(ux, uy)| {
+            let project = |corners: &[(f32, f32); 4]| {
+                corners
+                    .iter()
+                    .map(|(x, y)| x * ux + y * uy)
+                    .fold((f32::INFINITY, f32::NEG_INFINITY), |(lo, hi), p| {
+                        (lo.min(p), hi.max(p))
+                    })
+            };
+            let ((min_a, max_a), (min_b, max_b)) = (project(&mine), project(&theirs));
+            // A hair of tolerance so vehicles merely touching are not a collision
+            min_a < max_b - OVERLAP_TOLERANCE && min_b < max_a - OVERLAP_TOLERANCE
+        })
+    }
+}
+
+/// Overlap (px) below which two footprints are taken to be touching, not colliding.
+const OVERLAP_TOLERANCE: f32 = 0.01;
+
+/// Two vehicles whose footprints overlapped, recorded the first time they did.
+#[derive(Clone, Debug, Serialize)]
+pub struct CollisionRecord {
+    /// Simulation time (s).
+    pub time: f32,
+    /// The two vehicles, smaller id first.
+    pub vehicles: [usize; 2],
+    pub lanes: [Lane; 2],
+    /// Midpoint between the two vehicles' centres (px).
+    pub position: (f32, f32),
+}
+
+impl CollisionRecord {
+    pub fn new(time: f32, a: &Vehicle, b: &Vehicle) -> Self {
+        let (first, second) = if a.id < b.id { (a, b) } else { (b, a) };
+        let ((ax, ay), (bx, by)) = (first.centre(), second.centre());
+        CollisionRecord {
+            time,
+            vehicles: [first.id, second.id],
+            lanes: [first.lane, second.lane],
+            position: ((ax + bx) / 2.0, (ay + by) / 2.0),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Collision {
+    pub safe_distance: f32,
+}
+
+impl Default for Collision {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Collision {
+    pub fn new() -> Self {
+        Self {
+            safe_distance: 40.0,
+        }
+    }
+
+    pub fn check_vehicle_ahead<'a>(
+        vehicle: &'a Vehicle,
+        vehicles: &'a [Vehicle],
+    ) -> Option<&'a Vehicle> {
+        let check_distance = vehicle.collision.safe_distance + 20.0;
+
+        vehicles.iter().find(|other| {
+            other.id != vehicle.id
+                && Collision::road_gap(vehicle, other)
+                    .is_some_and(|gap| gap > 0.0 && gap <= check_distance)
+        })
+    }
+
+    /**
+     * How far `other` is ahead of `vehicle` along the road they share, `None`
+     * when they do not share one. They share the inbound lane when they make
+     * the same movement from it, or another movement whose tail has not yet
+     * turned off it; they share the outbound lane once `other` has joined it
+     * and `vehicle` is turning into it.
+     */
+    fn road_gap(vehicle: &Vehicle, other: &Vehicle) -> Option<f32> {
+        if vehicle.shares_lane_with(other) {
+            let shared = vehicle
+                .route
+                .straight_until()
+                .min(other.route.straight_until());
+            if other.lane == vehicle.lane || other.distance_travelled < shared + VEHICLE_SIZE {
+                return Some(other.distance_travelled - vehicle.distance_travelled);
+            }
+        }
+        if vehicle.lane.to == other.lane.to
+            && vehicle.slot == other.slot
+            && vehicle.distance_travelled >= vehicle.route.turn_start()
+            && other.distance_travelled >= other.route.joins_exit()
+        {
+            let left = |v: &Vehicle| v.route.map_exit - v.distance_travelled;
+            return Some(left(vehicle) - left(other));
+        }
+        None
+    }
+
+    /**
+     * Whether the paths of two vehicles cross or merge in the box. The same
+     * movement from two different inbound lanes runs side by side and does
+     * not cross. Movements the conflict table keeps apart may still meet
+     * where lanes are shared or the box is small, so their routes are
+     * compared too.
+     */
+    pub fn paths_cross(a: &Vehicle, b: &Vehicle, routes: &RouteConflicts) -> bool {
+        let parallel = a.lane == b.lane && a.slot != b.slot;
+        a.lane.conflicts_with(&b.lane) && !parallel || Self::paths_meet(a, b, routes)
+    }
+
+    /// Whether two vehicles on different movements come close enough to touch in the box.
+    pub fn paths_meet(a: &Vehicle, b: &Vehicle, routes: &RouteConflicts) -> bool {
+        a.lane != b.lane && routes.meet(&a.lane, a.slot, &b.lane, b.slot)
+    }
+
+    /// Index pairs of the vehicles whose footprints overlap, smaller index first.
+    pub fn overlapping_pairs(vehicles: &[Vehicle]) -> Vec<(usize, usize)> {
+        let boxes: Vec<Obb> = vehicles.iter().map(Obb::of_vehicle).collect();
+        let mut pairs = Vec::new();
+        for i in 0..boxes.len() {
+            for j in i + 1..boxes.len() {
+                if boxes[i].overlaps(&boxes[j]) {
+                    pairs.push((i, j));
+                }
+            }
+        }
+        pairs
+    }
+
+    /// Whether any part of the vehicle's footprint is inside the box.
+    pub fn is_vehicle_in_intersection(vehicle: &Vehicle) -> bool {
+        vehicle.geometry.overlaps_box(vehicle.x, vehicle.y)
+    }
+
+    pub fn has_vehicle_in_intersection(vehicles: &[Vehicle], exclude_id: usize) -> bool {
+        vehicles
+            .iter()
+            .any(|v| v.id != exclude_id && Self::is_vehicle_in_intersection(v))
+    }
+
+    /// A vehicle that has crossed its stop line but is not yet fully inside the box.
+    pub fn is_vehicle_entering_intersection(vehicle: &Vehicle) -> bool {
+        let distance = vehicle.distance_to_stop_line();
+        vehicle.state == VehicleState::Approaching && distance < 0.0 && distance > -30.0
+    }
+
+    /// A vehicle held at its stop line waiting to be let into the box.
+    pub fn is_vehicle_at_stop_line(vehicle: &Vehicle) -> bool {
+        let distance = vehicle.distance_to_stop_line();
+        vehicle.state == VehicleState::Waiting && distance <= 0.0 && distance > -2.0
+    }
+
+    /// The vehicles currently entering or inside the box, other than `exclude_id`.
+    pub fn occupying_vehicles(vehicles: &[Vehicle], exclude_id: usize) -> Vec<&Vehicle> {
+        vehicles
+            .iter()
+            .filter(|v| {
+                v.id != exclude_id
+                    && (Self::is_vehicle_in_intersection(v)
+                        || Self::is_vehicle_entering_intersection(v))
+            })
+            .collect()
+    }
+
+    /// A right turn waits only while a vehicle in or entering the box is on a route that meets its own.
+    pub fn is_right_turn_blocked(
+        vehicle: &Vehicle,
+        vehicles: &[Vehicle],
+        routes: &RouteConflicts,
+    ) -> bool {
+        Self::occupying_vehicles(vehicles, vehicle.id)
+            .iter()
+            .any(|other| Self::paths_meet(other, vehicle, routes))
+    }
+
+    pub fn should_wait_for_intersection(
+        vehicle: &Vehicle,
+        vehicles: &[Vehicle],
+        routes: &RouteConflicts,
+    ) -> bool {
+        // Right-turning vehicles can proceed unless another movement meets them in the box
+        if vehicle.lane.is_right_turn() {
+            return Self::is_right_turn_blocked(vehicle, vehicles, routes);
+        }
+
+        // Straight and left-turning vehicles wait only for movements that cross their path
+        if Self::occupying_vehicles(vehicles, vehicle.id)
+            .iter()
+            .any(|other| Self::paths_cross(other, vehicle, routes))
+        {
+            return true;
+        }
+
+        // ...and give way to conflicting vehicles that reached their stop line first,
+        // so a steady stream of compatible movements cannot starve the others
+        let arrived = vehicle.stop_line_time.unwrap_or(f32::MAX);
+        vehicles.iter().any(|other| {
+            other.id != vehicle.id
+                && Self::is_vehicle_at_stop_line(other)
+                && Self::paths_cross(other, vehicle, routes)
+                && (other.stop_line_time, other.id) < (Some(arrived), vehicle.id)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sim::IntersectionGeometry;
+    use crate::sim::types::Direction;
+    use rand::{SeedableRng, rngs::StdRng};
+    use std::f32::consts::{FRAC_PI_4, SQRT_2};
+
+    fn routes() -> RouteConflicts {
+        RouteConflicts::new(&IntersectionGeometry::STANDARD)
+    }
+
+    /// A vehicle on `lane`, `distance` short of its stop line (past it when negative).
+    fn vehicle(id: usize, lane: Lane, distance: f32) -> Vehicle {
+        let geometry = IntersectionGeometry::STANDARD;
+        let slot = geometry.lane_slots(&lane)[0];
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut vehicle = Vehicle::new(id, lane, slot, &geometry, 0.0, &mut rng);
+        vehicle.travel(vehicle.route.stop_line - distance);
+        vehicle
+    }
+
+    #[test]
+    fn waits_only_for_movements_crossing_its_path() {
+        use Direction::*;
+
+        let mut waiting = vehicle(1, Lane::set(North, South), 0.0);
+        waiting.state = VehicleState::Waiting;
+        waiting.stop_line_time = Some(0.0);
+
+        let crossing = vehicle(2, Lane::set(East, West), -135.0);
+        assert!(Collision::should_wait_for_intersection(
+            &waiting,
+            &[waiting.clone(), crossing],
+            &routes()
+        ));
+
+        let opposing = vehicle(3, Lane::set(South, North), -135.0);
+        assert!(!Collision::should_wait_for_intersection(
+            &waiting,
+            &[waiting.clone(), opposing],
+            &routes()
+        ));
+    }
+
+    #[test]
+    fn gives_way_to_a_conflicting_vehicle_that_stopped_first() {
+        use Direction::*;
+
+        let mut first = vehicle(1, Lane::set(East, West), 0.0);
+        first.state = VehicleState::Waiting;
+        first.stop_line_time = Some(1.0);
+        let mut second = vehicle(2, Lane::set(North, South), 0.0);
+        second.state = VehicleState::Waiting;
+        second.stop_line_time = Some(2.0);
+
+        let vehicles = [first.clone(), second.clone()];
+        assert!(Collision::should_wait_for_intersection(
+            &second,
+            &vehicles,
+            &routes()
+        ));
+        assert!(!Collision::should_wait_for_intersection(
+            &first,
+            &vehicles,
+            &routes()
+        ));
+    }
+
+    const S: f32 = VEHICLE_SIZE;
+
+    #[test]
+    fn squares_side_by_side_overlap_only_when_closer_than_their_size() {
+        let a = Obb::square((100.0, 100.0), 0.0);
+        assert!(a.overlaps(&Obb::square((100.0 + S - 1.0, 100.0), 0.0)));
+        assert!(!a.overlaps(&Obb::square((100.0 + S, 100.0), 0.0)));
+        assert!(!a.overlaps(&Obb::square((100.0 + S + 1.0, 100.0), 0.0)));
+        assert!(a.overlaps(&Obb::square((100.0 + S - 1.0, 100.0 + S - 1.0), 0.0)));
+        assert!(!a.overlaps(&Obb::square((100.0 + S - 1.0, 100.0 + S + 1.0), 0.0)));
+    }
+
+    #[test]
+    fn turned_square_reaches_out_by_its_half_diagonal() {
+        let turned = Obb::square((100.0, 100.0), FRAC_PI_4);
+        let reach = S / 2.0 * SQRT_2 + S / 2.0;
+        assert!(turned.overlaps(&Obb::square((100.0 + reach - 1.0, 100.0), 0.0)));
+        assert!(!turned.overlaps(&Obb::square((100.0 + reach + 1.0, 100.0), 0.0)));
+
+        // Two diamonds side by side meet tip to tip
+        let reach = S * SQRT_2;
+        assert!(turned.overlaps(&Obb::square((100.0 + reach - 1.0, 100.0), FRAC_PI_4)));
+        assert!(!turned.overlaps(&Obb::square((100.0 + reach + 1.0, 100.0), FRAC_PI_4)));
+    }
+
+    #[test]
+    fn turned_square_off_a_corner_is_separated_by_its_own_axis() {
+        let square_box = Obb::square((100.0, 100.0), 0.0);
+        // Their bounding boxes overlap at this offset, yet the diamond's side
+        // stays clear of the square's corner
+        let clear = Obb::square((100.0 + S, 100.0 + S), FRAC_PI_4);
+        assert!(!square_box.overlaps(&clear));
+        assert!(!clear.overlaps(&square_box));
+
+        let overlapping = Obb::square((100.0 + 0.8 * S, 100.0 + 0.8 * S), FRAC_PI_4);
+        assert!(square_box.overlaps(&overlapping));
+        assert!(overlapping.overlaps(&square_box));
+    }
+
+    #[test]
+    fn overlapping_vehicles_are_recorded_smaller_id_first() {
+        use Direction::*;
+
+        let vehicles = [
+            vehicle(7, Lane::set(West, East), -77.5),
+            vehicle(2, Lane::set(North, South), -172.5),
+            vehicle(5, Lane::set(South, North), 200.0),
+        ];
+        assert_eq!(Collision::overlapping_pairs(&vehicles), [(0, 1)]);
+
+        let record = CollisionRecord::new(4.0, &vehicles[0], &vehicles[1]);
+        assert_eq!(record.vehicles, [2, 7]);
+        assert_eq!(
+            record.lanes,
+            [Lane::set(North, South), Lane::set(West, East)]
+        );
+        assert_eq!(record.position, (347.5, 447.5));
+    }
+}
@@ -0,0 +1,149 @@
+use serde::Serialize;
+
+/// Summary of a sample of values: moments, extremes and percentiles.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Serialize)]
+pub struct Distribution {
+    pub count: usize,
+    pub mean: f32,
+    /// Population standard deviation.
+    pub std_dev: f32,
+    pub min: f32,
+    pub max: f32,
+    pub p50: f32,
+    pub p90: f32,
+    pub p99: f32,
+}
+
+impl Distribution {
+    /// Summarises `values`; all fields are zero for an empty sample.
+    pub fn from_values(values: &[f32]) -> Self {
+        if values.is_empty() {
+            return Distribution::default();
+        }
+
+        let mut sorted = values.to_vec();
+        sorted.sort_by(f32::total_cmp);
+
+        let count = sorted.len();
+        let mean = sorted.iter().sum::<f32>() / count as f32;
+        let variance = sorted.iter().map(|v| (v - mean).powi(2)).sum::<f32>() / count as f32;
+
+        Distribution {
+            count,
+            mean,
+            std_dev: variance.sqrt(),
+            min: sorted[0],
+            max: sorted[count - 1],
+            p50: percentile(&sorted, 50.0),
+            p90: percentile(&sorted, 90.0),
+            p99: percentile(&sorted, 99.0),
+        }
+    }
+}
+
+/// Nearest-rank percentile of an ascending, non-empty slice.
+fn percentile(sorted: &[f32], percent: f32) -> f32 {
+    let rank = (percent / 100.0 * sorted.len() as f32).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+/// Counts of values falling in equal-width bins between the sample's min and max.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Histogram {
+    pub start: f32,
+    pub bin_width: f32,
+    pub counts: Vec<usize>,
+}
+
+impl Histogram {
+    pub fn new(values: &[f32], bins: usize) -> Self {
+        let bins = bins.max(1);
+        let min = values.iter().copied().fold(f32::INFINITY, f32::min);
+        let max = values.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+        if values.is_empty() {
+            return Histogram {
+                start: 0.0,
+                bin_width: 1.0,
+                counts: vec![0; bins],
+            };
+        }
+
+        // A sample of identical values still gets a bin of non-zero width
+        let bin_width = ((max - min) / bins as f32).max(f32::EPSILON);
+        let mut counts = vec![0; bins];
+        for value in values {
+            let bin = ((value - min) / bin_width) as usize;
+            counts[bin.min(bins - 1)] += 1;
+        }
+
+        Histogram {
+            start: min,
+            bin_width,
+            counts,
+        }
+    }
+
+    pub fn end(&self) -> f32 {
+        self.start + self.bin_width * self.counts.len() as f32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn percentiles_take_the_nearest_rank() {
+        let hundred: Vec<f32> = (1..=100).rev().map(|v| v as f32).collect();
+        let distribution = Distribution::from_values(&hundred);
+        assert_eq!(distribution.count, 100);
+        assert_eq!((distribution.min, distribution.max), (1.0, 100.0));
+        assert_eq!(
+            (distribution.p50, distribution.p90, distribution.p99),
+            (50.0, 90.0, 99.0)
+        );
+
+        let ten = [7.0, 3.0, 10.0, 1.0, 5.0, 9.0, 2.0, 8.0, 4.0, 6.0];
+        let distribution = Distribution::from_values(&ten);
+        assert_eq!(
+            (distribution.p50, distribution.p90, distribution.p99),
+            (5.0, 9.0, 10.0)
+        );
+
+        let one = Distribution::from_values(&[4.0]);
+        assert_eq!((one.p50, one.p90, one.p99), (4.0, 4.0, 4.0));
+    }
+
+    #[test]
+    fn moments_are_of_the_whole_population() {
+        let distribution = Distribution::from_values(&[2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0]);
+        assert_eq!(distribution.mean, 5.0);
+        assert_eq!(distribution.std_dev, 2.0);
+    }
+
+    #[test]
+    fn empty_sample_is_all_zero() {
+        assert_eq!(Distribution::from_values(&[]), Distribution::default());
+    }
+
+    #[test]
+    fn histogram_puts_the_maximum_in_the_last_bin() {
+        let values: Vec<f32> = (0..=10).map(|v| v as f32).collect();
+        let histogram = Histogram::new(&values, 5);
+        assert_eq!((histogram.start, histogram.bin_width), (0.0, 2.0));
+        assert_eq!(histogram.counts, vec![2, 2, 2, 2, 3]);
+        assert_eq!(histogram.end(), 10.0);
+    }
+
+    #[test]
+    fn histogram_of_identical_or_no_values() {
+        let same = Histogram::new(&[3.0, 3.0, 3.0], 4);
+        assert_eq!(same.start, 3.0);
+        assert!(same.bin_width > 0.0);
+        assert_eq!(same.counts, vec![3, 0, 0, 0]);
+
+        let empty = Histogram::new(&[], 0);
+        assert_eq!(empty.counts, vec![0]);
+        assert_eq!(empty.end(), 1.0);
+    }
+}
@@ -0,0 +1,460 @@
+use crate::sim::types::VehicleState;
+use crate::sim::{Lane, SafetyMeasure};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+/// Importance of an event; subscribers receive their level and above.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Level {
+    Trace,
+    Debug,
+    Info,
+    Warn,
+}
+
+/// Level names accepted by `Level::from_name`, least important first.
+pub const LEVEL_NAMES: [&str; 4] = ["trace", "debug", "info", "warn"];
+
+impl Level {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "trace" => Some(Level::Trace),
+            "debug" => Some(Level::Debug),
+            "info" => Some(Level::Info),
+            "warn" => Some(Level::Warn),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(LEVEL_NAMES[*self as usize])
+    }
+}
+
+/// Something that happened in the simulation.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum SimEvent {
+    Spawned {
+        id: usize,
+        lane: Lane,
+        x: f32,
+        y: f32,
+        speed: f32,
+    },
+    /// A spawn waiting for its lane entry to clear.
+    SpawnDeferred {
+        lane: Lane,
+    },
+    EnteredIntersection {
+        id: usize,
+        lane: Lane,
+    },
+    /// A vehicle left the screen, `travel_time` seconds after spawning.
+    Exited {
+        id: usize,
+        lane: Lane,
+        travel_time: f32,
+    },
+    /// A vehicle carried on from one intersection of a road network to the next.
+    HandedOff {
+        id: usize,
+        /// Its movement through the next intersection.
+        lane: Lane,
+        column: usize,
+        row: usize,
+    },
+    /// Reported once per pair of vehicles, `value` being the TTC or PET (s) that flagged it.
+    CloseCall {
+        id: usize,
+        other: usize,
+        measure: SafetyMeasure,
+        value: f32,
+    },
+    StateChanged {
+        id: usize,
+        from: VehicleState,
+        to: VehicleState,
+    },
+    /// The footprints of two vehicles overlap, reported once per pair.
+    Collision {
+        id: usize,
+        other: usize,
+        x: f32,
+        y: f32,
+    },
+    /// Random spawning was switched on or off from the keyboard.
+    RandomSpawning {
+        enabled: bool,
+    },
+    /// A replayed recording ran out and the keyboard took over.
+    ReplayFinished,
+}
+
+impl SimEvent {
+    pub fn level(&self) -> Level {
+        match self {
+            SimEvent::StateChanged { .. } => Level::Trace,
+            SimEvent::Spawned { .. }
+            | SimEvent::EnteredIntersection { .. }
+            | SimEvent::Exited { .. }
+            | SimEvent::HandedOff { .. }
+            | SimEvent::RandomSpawning { .. } => Level::Debug,
+            SimEvent::SpawnDeferred { .. } | SimEvent::ReplayFinished => Level::Info,
+            SimEvent::CloseCall { .. } | SimEvent::Collision { .. } => Level::Warn,
+        }
+    }
+}
+
+impl fmt::Display for SimEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SimEvent::Spawned {
+                id,
+                lane,
+                x,
+                y,
+                speed,
+            } => write!(
+                f,
+                "vehicle {} spawned on {:?}->{:?} at ({:.1}, {:.1}), {:.1} px/s",
+                id, lane.from, lane.to, x, y, speed
+            ),
+            SimEvent::SpawnDeferred { lane } => write!(
+                f,
+                "lane {:?}->{:?} congested, delaying spawn",
+                lane.from, lane.to
+            ),
+            SimEvent::EnteredIntersection { id, lane } => write!(
+                f,
+                "vehicle {} entered the intersection ({:?}->{:?})",
+                id, lane.from, lane.to
+            ),
+            SimEvent::Exited {
+                id,
+                lane,
+                travel_time,
+            } => write!(
+                f,
+                "vehicle {} left ({:?}->{:?}) after {:.2} s",
+                id, lane.from, lane.to, travel_time
+            ),
+            SimEvent::HandedOff {
+                id,
+                lane,
+                column,
+                row,
+            } => write!(
+                f,
+                "vehicle {} handed off to intersection ({}, {}) on {:?}->{:?}",
+                id, column, row, lane.from, lane.to
+            ),
+            SimEvent::CloseCall {
+                id,
+                other,
+                measure,
+                value,
+            } => write!(
+                f,
+                "close call between vehicles {} and {} ({} {:.2} s)",
+                id,
+                other,
+                format!("{:?}", measure).to_uppercase(),
+                value
+            ),
+            SimEvent::StateChanged { id, from, to } => {
+                write!(f, "vehicle {} {:?} -> {:?}", id, from, to)
+            }
+            SimEvent::Collision { id, other, x, y } => write!(
+                f,
+                "collision between vehicles {} and {} at ({:.1}, {:.1})",
+                id, other, x, y
+            ),
+            SimEvent::RandomSpawning { enabled } => {
+                write!(f, "random spawning {}", if *enabled { "on" } else { "off" })
+            }
+            SimEvent::ReplayFinished => write!(f, "replay finished, the keyboard takes over"),
+        }
+    }
+}
+
+/// An event stamped with when it happened.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Event {
+    pub tick: u64,
+    /// Simulation time (s).
+    pub time: f32,
+    pub level: Level,
+    #[serde(flatten)]
+    pub kind: SimEvent,
+}
+
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:>9.2}s {:<5} {}", self.time, self.level, self.kind)
+    }
+}
+
+/// Receives the events a subscriber asked for. Closures taking an `&Event` are sinks too.
+pub trait EventSink {
+    fn handle(&mut self, event: &Event);
+
+    /// Pushes out anything buffered; called at the end of a run.
+    fn flush(&mut self) {}
+}
+
+impl<F: FnMut(&Event)> EventSink for F {
+    fn handle(&mut self, event: &Event) {
+        self(event)
+    }
+}
+
+struct Subscriber {
+    level: Level,
+    sink: Box<dyn EventSink>,
+}
+
+/// Hands every event to the subscribers whose level it reaches.
+#[derive(Default)]
+pub struct EventBus {
+    subscribers: Vec<Subscriber>,
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        EventBus {
+            subscribers: Vec::new(),
+        }
+    }
+
+    /// Sends events of `level` and above to `sink`.
+    pub fn subscribe(&mut self, level: Level, sink: impl EventSink + 'static) {
+        self.subscribers.push(Subscriber {
+            level,
+            sink: Box::new(sink),
+        });
+    }
+
+    pub fn emit(&mut self, tick: u64, time: f32, kind: SimEvent) {
+        let level = kind.level();
+        if self.subscribers.iter().all(|s| level < s.level) {
+            return;
+        }
+        let event = Event {
+            tick,
+            time,
+            level,
+            kind,
+        };
+        for subscriber in &mut self.subscribers {
+            if level >= subscriber.level {
+                subscriber.sink.handle(&event);
+            }
+        }
+    }
+
+    pub fn flush(&mut self) {
+        for subscriber in &mut self.subscribers {
+            subscriber.sink.flush();
+        }
+    }
+}
+
+/// Writes events as text lines, or as JSON Lines, to stderr or a file.
+pub struct LogSink {
+    out: Box<dyn Write>,
+    json: bool,
+    failed: bool,
+}
+
+impl LogSink {
+    pub fn new(out: Box<dyn Write>, json: bool) -> Self {
+        LogSink {
+            out,
+            json,
+            failed: false,
+        }
+    }
+
+    /// Text lines on stderr.
+    pub fn stderr() -> Self {
+        LogSink::new(Box::new(std::io::stderr()), false)
+    }
+
+    /**
+     * Creates a sink writing to `path`, or to stderr for `-`. Files ending in
+     * `.jsonl` or `.json` get JSON Lines, anything else text lines.
+     *
+     * # Returns
+     *
+     * A Result with an error string if the file could not be created.
+     */
+    pub fn create(path: &str) -> Result<Self, String> {
+        if path == "-" {
+            return Ok(LogSink::stderr());
+        }
+        let json = Path::new(path)
+            .extension()
+            .and_then(|e| e.to_str())
+            .is_some_and(|e| e == "jsonl" || e == "json");
+        let file =
+            File::create(path).map_err(|e| format!("cannot create event log {}: {}", path, e))?;
+        Ok(LogSink::new(Box::new(BufWriter::new(file)), json))
+    }
+
+    fn write(&mut self, event: &Event) -> std::io::Result<()> {
+        if self.json {
+            serde_json::to_writer(&mut self.out, event)?;
+            writeln!(self.out)
+        } else {
+            writeln!(self.out, "{}", event)
+        }
+    }
+}
+
+impl EventSink for LogSink {
+    fn handle(&mut self, event: &Event) {
+        // Report a broken log once rather than on every event
+        if !self.failed
+            && let Err(e) = self.write(event)
+        {
+            eprintln!("cannot write event log: {}", e);
+            self.failed = true;
+        }
+    }
+
+    fn flush(&mut self) {
+        if !self.failed
+            && let Err(e) = self.out.flush()
+        {
+            eprintln!("cannot write event log: {}", e);
+            self.failed = true;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sim::types::Direction;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    const LANE: Lane = Lane::set(Direction::West, Direction::East);
+
+    const CLOSE_CALL: SimEvent = SimEvent::CloseCall {
+        id: 3,
+        other: 4,
+        measure: SafetyMeasure::Ttc,
+        value: 0.8,
+    };
+
+    /// Subscribes a sink at `level` and returns what it receives.
+    fn collect(bus: &mut EventBus, level: Level) -> Rc<RefCell<Vec<Event>>> {
+        let received = Rc::new(RefCell::new(Vec::new()));
+        let sink = Rc::clone(&received);
+        bus.subscribe(level, move |event: &Event| {
+            sink.borrow_mut().push(event.clone())
+        });
+        received
+    }
+
+    #[test]
+    fn subscribers_receive_their_level_and_above() {
+        let mut bus = EventBus::new();
+        let everything = collect(&mut bus, Level::Trace);
+        let warnings = collect(&mut bus, Level::Warn);
+
+        bus.emit(1, 0.5, SimEvent::SpawnDeferred { lane: LANE });
+        bus.emit(2, 1.0, CLOSE_CALL);
+        bus.emit(
+            3,
+            1.5,
+            SimEvent::StateChanged {
+                id: 3,
+                from: VehicleState::Approaching,
+                to: VehicleState::Waiting,
+            },
+        );
+
+        assert_eq!(everything.borrow().len(), 3);
+        let warnings = warnings.borrow();
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].tick, 2);
+        assert_eq!(warnings[0].level, Level::Warn);
+        assert_eq!(warnings[0].kind, CLOSE_CALL);
+    }
+
+    #[test]
+    fn levels_are_ordered_and_named() {
+        assert!(Level::Trace < Level::Debug && Level::Info < Level::Warn);
+        for name in LEVEL_NAMES {
+            assert_eq!(Level::from_name(name).unwrap().to_string(), name);
+        }
+        assert_eq!(Level::from_name("error"), None);
+        assert_eq!(
+            SimEvent::Exited {
+                id: 0,
+                lane: LANE,
+                travel_time: 1.0
+            }
+            .level(),
+            Level::Debug
+        );
+        assert_eq!(SimEvent::ReplayFinished.level(), Level::Info);
+        assert_eq!(
+            SimEvent::RandomSpawning { enabled: true }.level(),
+            Level::Debug
+        );
+    }
+
+    #[test]
+    fn events_print_as_text_or_flattened_json() {
+        let event = Event {
+            tick: 150,
+            time: 2.5,
+            level: Level::Debug,
+            kind: SimEvent::Exited {
+                id: 7,
+                lane: LANE,
+                travel_time: 12.345,
+            },
+        };
+        assert_eq!(
+            event.to_string(),
+            "     2.50s debug vehicle 7 left (West->East) after 12.35 s"
+        );
+
+        assert_eq!(
+            CLOSE_CALL.to_string(),
+            "close call between vehicles 3 and 4 (TTC 0.80 s)"
+        );
+        assert_eq!(
+            SimEvent::HandedOff {
+                id: 7,
+                lane: LANE,
+                column: 2,
+                row: 1,
+            }
+            .to_string(),
+            "vehicle 7 handed off to intersection (2, 1) on West->East"
+        );
+        assert_eq!(
+            SimEvent::RandomSpawning { enabled: false }.to_string(),
+            "random spawning off"
+        );
+
+        let json: serde_json::Value = serde_json::to_value(&event).unwrap();
+        assert_eq!(json["event"], "exited");
+        assert_eq!(json["level"], "debug");
+        assert_eq!(json["tick"], 150);
+        assert_eq!(json["id"], 7);
+        assert_eq!(json["lane"]["from"], "west");
+    }
+}
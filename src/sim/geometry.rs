@@ -0,0 +1,874 @@
+use crate::sim::types::Direction;
+use crate::sim::{Lane, Obb, Turn, VEHICLE_SIZE};
+use serde::{Deserialize, Serialize};
+use std::f32::consts::{FRAC_PI_2, SQRT_2};
+use std::fmt;
+
+/// Interval between the footprints compared when checking whether two routes meet (px).
+const ROUTE_SAMPLE_STEP: f32 = 1.0;
+
+/// Most inbound lanes an approach can have.
+pub const MAX_LANES: usize = 4;
+
+/// The movements an inbound lane may be used for.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct LaneUse {
+    turns: [bool; 3],
+}
+
+impl LaneUse {
+    pub fn only(turn: Turn) -> Self {
+        let mut lane_use = LaneUse::default();
+        lane_use.turns[turn as usize] = true;
+        lane_use
+    }
+
+    pub fn allows(&self, turn: Turn) -> bool {
+        self.turns[turn as usize]
+    }
+
+    /// The movements allowed, from the road edge inwards.
+    pub fn turns(&self) -> impl Iterator<Item = Turn> + '_ {
+        Turn::ALL.into_iter().filter(|turn| self.allows(*turn))
+    }
+
+    /// Reads letters for the allowed movements, e.g. `rs` for a shared straight and right lane.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut lane_use = LaneUse::default();
+        for letter in text.chars() {
+            let turn = Turn::from_letter(letter)
+                .ok_or_else(|| format!("invalid lane use {:?}: letters must be r, s or l", text))?;
+            if lane_use.allows(turn) {
+                return Err(format!(
+                    "invalid lane use {:?}: {} given twice",
+                    text, letter
+                ));
+            }
+            lane_use.turns[turn as usize] = true;
+        }
+        if lane_use.turns().next().is_none() {
+            return Err("a lane must allow at least one movement".to_string());
+        }
+        Ok(lane_use)
+    }
+}
+
+impl fmt::Display for LaneUse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let letters: String = self.turns().map(|turn| turn.letter()).collect();
+        f.pad(&letters)
+    }
+}
+
+/**
+ * The inbound lanes of each approach, from the road edge to the centre line,
+ * and the movements each one may be used for. Every approach has the same
+ * layout.
+ *
+ * Written as comma-separated lane uses, `r,s,l` for the standard layout or
+ * `rs,s,l,l` for a shared straight and right lane beside two left lanes.
+ * Movements may not cross within an approach, so right turns keep to the
+ * lanes nearest the edge and left turns to those nearest the centre line.
+ */
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "Vec<String>", into = "Vec<String>")]
+pub struct LaneLayout {
+    count: usize,
+    lanes: [LaneUse; MAX_LANES],
+}
+
+impl LaneLayout {
+    /// One lane each for right, straight and left.
+    pub const STANDARD: LaneLayout = LaneLayout {
+        count: 3,
+        lanes: [
+            LaneUse {
+                turns: [true, false, false],
+            },
+            LaneUse {
+                turns: [false, true, false],
+            },
+            LaneUse {
+                turns: [false, false, true],
+            },
+            LaneUse {
+                turns: [false, false, false],
+            },
+        ],
+    };
+
+    /**
+     * Checks and builds a layout from lane uses ordered from the road edge.
+     *
+     * # Returns
+     *
+     * A Result with an error string if there are no lanes or more than
+     * `MAX_LANES`, a movement has no lane, movements would cross, or the box
+     * is too small for the movements allowed.
+     */
+    pub fn new(lanes: &[LaneUse]) -> Result<Self, String> {
+        if lanes.is_empty() || lanes.len() > MAX_LANES {
+            return Err(format!(
+                "an approach needs 1 to {} lanes, not {}",
+                MAX_LANES,
+                lanes.len()
+            ));
+        }
+        for turn in Turn::ALL {
+            if !lanes.iter().any(|lane| lane.allows(turn)) {
+                return Err(format!("no lane allows {:?} movements", turn));
+            }
+        }
+        for pair in lanes.windows(2) {
+            let outer = pair[0].turns().max();
+            let inner = pair[1].turns().min();
+            if outer > inner {
+                return Err(format!(
+                    "lane {} next to lane {} would cross: right turns go nearest the edge, \
+                     left turns nearest the centre line",
+                    pair[0], pair[1]
+                ));
+            }
+        }
+
+        let mut layout = LaneLayout {
+            count: lanes.len(),
+            lanes: [LaneUse::default(); MAX_LANES],
+        };
+        layout.lanes[..lanes.len()].copy_from_slice(lanes);
+        IntersectionGeometry {
+            lanes: layout,
+            ..IntersectionGeometry::STANDARD
+        }
+        .check_turns()?;
+        Ok(layout)
+    }
+
+    /// Reads a layout written as comma-separated lane uses, e.g. `rs,s,l,l`.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let lanes = text
+            .split(',')
+            .map(|lane| LaneUse::parse(lane.trim()))
+            .collect::<Result<Vec<_>, _>>()?;
+        Self::new(&lanes)
+    }
+
+    pub fn lanes(&self) -> &[LaneUse] {
+        &self.lanes[..self.count]
+    }
+
+    pub fn len(&self) -> usize {
+        self.count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    /// Slots, counted from the road edge, of the lanes `turn` may use.
+    pub fn slots_for(&self, turn: Turn) -> impl Iterator<Item = usize> + '_ {
+        self.lanes()
+            .iter()
+            .enumerate()
+            .filter(move |(_, lane)| lane.allows(turn))
+            .map(|(slot, _)| slot)
+    }
+}
+
+impl Default for LaneLayout {
+    fn default() -> Self {
+        Self::STANDARD
+    }
+}
+
+impl fmt::Display for LaneLayout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let lanes: Vec<String> = self.lanes().iter().map(|lane| lane.to_string()).collect();
+        f.pad(&lanes.join(","))
+    }
+}
+
+impl TryFrom<Vec<String>> for LaneLayout {
+    type Error = String;
+
+    fn try_from(lanes: Vec<String>) -> Result<Self, String> {
+        let lanes = lanes
+            .iter()
+            .map(|lane| LaneUse::parse(lane))
+            .collect::<Result<Vec<_>, _>>()?;
+        Self::new(&lanes)
+    }
+}
+
+impl From<LaneLayout> for Vec<String> {
+    fn from(layout: LaneLayout) -> Self {
+        layout.lanes().iter().map(|lane| lane.to_string()).collect()
+    }
+}
+
+/**
+ * The layout of the intersection: a square map crossed by two roads meeting
+ * in a square box at its centre. Spawn points, routes, the box bounds and the
+ * road markings are all derived from these sizes.
+ */
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct IntersectionGeometry {
+    /// Side of the square map (px).
+    pub size: f32,
+    pub lane_width: f32,
+    /// Inbound lanes on each approach; as many lanes leave on each side.
+    pub lanes: LaneLayout,
+    /// Gap left between a vehicle held at the stop line and the box (px).
+    pub stop_line_offset: f32,
+    /// How far past the edge of the map a vehicle is removed (px).
+    pub despawn_margin: f32,
+}
+
+impl IntersectionGeometry {
+    /// The layout the simulation was designed around: 800 px map, three 35 px lanes each way.
+    pub const STANDARD: IntersectionGeometry = IntersectionGeometry {
+        size: 800.0,
+        lane_width: 35.0,
+        lanes: LaneLayout::STANDARD,
+        stop_line_offset: 10.0,
+        despawn_margin: 50.0,
+    };
+
+    pub fn centre(&self) -> (f32, f32) {
+        (self.size / 2.0, self.size / 2.0)
+    }
+
+    pub fn lanes_per_approach(&self) -> usize {
+        self.lanes.len()
+    }
+
+    /// Width of the inbound half of a road (px).
+    pub fn half_road(&self) -> f32 {
+        self.lane_width * self.lanes_per_approach() as f32
+    }
+
+    /// Lowest x and y of the box.
+    pub fn box_min(&self) -> f32 {
+        self.size / 2.0 - self.half_road()
+    }
+
+    /// Highest x and y of the box.
+    pub fn box_max(&self) -> f32 {
+        self.size / 2.0 + self.half_road()
+    }
+
+    /// Whether the footprint of a vehicle whose top-left corner is at (x, y) overlaps the box.
+    pub fn overlaps_box(&self, x: f32, y: f32) -> bool {
+        let overlaps = |start: f32| start + VEHICLE_SIZE > self.box_min() && start < self.box_max();
+        overlaps(x) && overlaps(y)
+    }
+
+    /**
+     * Checks that the box is large enough for every movement the layout
+     * allows: a vehicle driving through it keeps on the road and clear of
+     * the vehicles held at the stop lines of the other approaches.
+     */
+    pub fn check_turns(&self) -> Result<(), String> {
+        let (low, high) = (self.box_min(), self.box_max());
+        // Half a pixel of slack for rounding in the corners of turned footprints
+        let on_road = |(x, y): (f32, f32)| {
+            let within = |value: f32| value > low - 0.5 && value < high + 0.5;
+            within(x) || within(y)
+        };
+        let mut held = Vec::new();
+        for lane in Lane::ALL {
+            for slot in self.lane_slots(&lane) {
+                let route = self.route(&lane, slot);
+                let (centre, angle) = route.pose_at(route.stop_line);
+                held.push((lane.from, Obb::square(centre, angle)));
+            }
+        }
+        let held_elsewhere = |approach: Direction| -> Vec<Obb> {
+            held.iter()
+                .filter(|(from, _)| *from != approach)
+                .map(|(_, footprint)| *footprint)
+                .collect()
+        };
+
+        for lane in Lane::ALL {
+            for slot in self.lane_slots(&lane) {
+                let footprints = self.route(&lane, slot).footprints_in_box();
+                let problem = if footprints
+                    .iter()
+                    .any(|footprint| !footprint.corners().into_iter().all(on_road))
+                {
+                    "would leave the road"
+                } else if footprints_meet(&footprints, &held_elsewhere(lane.from)) {
+                    "would hit vehicles held at the other stop lines"
+                } else {
+                    continue;
+                };
+                return Err(format!(
+                    "lanes {}: the box is too small for {:?}->{:?} from lane {}, which {}",
+                    self.lanes,
+                    lane.from,
+                    lane.to,
+                    slot + 1,
+                    problem
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether a vehicle whose top-left corner is at (x, y) is far enough off the map to remove.
+    pub fn is_off_map(&self, x: f32, y: f32) -> bool {
+        let low = -self.despawn_margin - VEHICLE_SIZE;
+        let high = self.size + self.despawn_margin;
+        x < low || x > high || y < low || y > high
+    }
+
+    /// The inbound lanes of its approach that `lane` may use, counted from the edge of the road.
+    pub fn lane_slots(&self, lane: &Lane) -> Vec<usize> {
+        self.lanes.slots_for(lane.turn()).collect()
+    }
+
+    /**
+     * Position across the road of the centre of inbound lane `slot` of
+     * approach `from`; an x for north and south, a y for east and west.
+     * Traffic keeps to the right, so slot 0 is on the driver's right.
+     */
+    pub fn lane_centre(&self, from: Direction, slot: usize) -> f32 {
+        let offset = self.lane_width * (slot as f32 + 0.5);
+        match from {
+            Direction::North | Direction::East => self.box_min() + offset,
+            Direction::South | Direction::West => self.box_max() - offset,
+        }
+    }
+
+    /// Top-left corner of a vehicle entering inbound lane `slot` for `lane`, just off the map.
+    pub fn spawn_point(&self, lane: &Lane, slot: usize) -> (f32, f32) {
+        let half = VEHICLE_SIZE / 2.0;
+        let ((x, y), _) = self.route(lane, slot).pose_at(0.0);
+        (x - half, y - half)
+    }
+
+    /**
+     * The path driven by a vehicle making the movement `lane` from inbound
+     * lane `slot`, from its spawn point to the edge of the map.
+     *
+     * Turns follow a quarter circle, as wide as the box allows, joining the
+     * centre line of the inbound lane to that of the outbound lane in the
+     * same slot on the far side.
+     */
+    pub fn route(&self, lane: &Lane, slot: usize) -> Route {
+        let half = VEHICLE_SIZE / 2.0;
+        let across = self.lane_centre(lane.from, slot);
+        let entry = lane.from.opposite().unit();
+        let exit = lane.to.unit();
+
+        // Spawned with the front of the vehicle on the edge of the map
+        let along = match lane.from {
+            Direction::North | Direction::West => -half,
+            Direction::South | Direction::East => self.size + half,
+        };
+        let start = if entry.0 == 0.0 {
+            (across, along)
+        } else {
+            (along, across)
+        };
+        let to_box = self.distance_to_box_edge(start, entry, false);
+        let stop_line = to_box - half - self.stop_line_offset;
+
+        // Where the centre lines of the inbound and outbound lanes cross
+        let exit_across = self.lane_centre(lane.to.opposite(), slot);
+        let corner = if lane.is_straight() {
+            start
+        } else if entry.0 == 0.0 {
+            (start.0, exit_across)
+        } else {
+            (exit_across, start.1)
+        };
+        let radius = if lane.is_straight() {
+            0.0
+        } else {
+            let back = (-entry.0, -entry.1);
+            self.distance_to_box_edge(corner, back, true)
+                .min(self.distance_to_box_edge(corner, exit, true))
+                .max(0.0)
+        };
+        let to_corner = (corner.0 - start.0).abs() + (corner.1 - start.1).abs();
+        let turn_start = to_corner - radius;
+
+        // The rear leaves the box half a vehicle after the centre crosses its edge
+        let arc = radius * FRAC_PI_2;
+        let turn_end = (corner.0 + radius * exit.0, corner.1 + radius * exit.1);
+        let box_exit = turn_start + arc + self.distance_to_box_edge(turn_end, exit, true) + half;
+        // The arms outside the box are as long on every side
+        let map_exit = box_exit + self.box_min() - VEHICLE_SIZE;
+
+        Route {
+            start,
+            entry,
+            exit,
+            turn_start,
+            radius,
+            stop_line,
+            box_exit,
+            map_exit,
+        }
+    }
+
+    /**
+     * Distance from `point` to the edge of the box met going along the unit
+     * vector `towards`: the far edge when `point` is inside the box, the near
+     * one when it is outside.
+     */
+    fn distance_to_box_edge(&self, point: (f32, f32), towards: (f32, f32), inside: bool) -> f32 {
+        let (low, high) = (self.box_min(), self.box_max());
+        let (value, sign) = if towards.0 != 0.0 {
+            (point.0, towards.0)
+        } else {
+            (point.1, towards.1)
+        };
+        match (sign > 0.0, inside) {
+            (true, true) => high - value,
+            (true, false) => low - value,
+            (false, true) => value - low,
+            (false, false) => value - high,
+        }
+    }
+}
+
+impl Default for IntersectionGeometry {
+    fn default() -> Self {
+        Self::STANDARD
+    }
+}
+
+/**
+ * The centre line driven along a lane: straight in, a quarter circle through
+ * the box and straight out, measured by distance from the spawn point.
+ */
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Route {
+    /// Centre of a vehicle at the spawn point.
+    start: (f32, f32),
+    /// Unit vectors of travel before and after the turn.
+    entry: (f32, f32),
+    exit: (f32, f32),
+    /// Distance at which the turn begins (px).
+    turn_start: f32,
+    /// Radius of the turn, zero for straight lanes (px).
+    radius: f32,
+    /// Distance at which the front of a vehicle reaches the stop line (px).
+    pub stop_line: f32,
+    /// Distance at which the rear of a vehicle leaves the box (px).
+    pub box_exit: f32,
+    /// Distance at which the front of a vehicle reaches the edge of the map (px).
+    pub map_exit: f32,
+}
+
+impl Route {
+    /// Distance driven along the inbound lane before turning off it, unbounded for straight routes.
+    pub fn straight_until(&self) -> f32 {
+        if self.entry == self.exit {
+            f32::INFINITY
+        } else {
+            self.turn_start
+        }
+    }
+
+    /// Distance at which the route leaves the centre line of its inbound lane, zero when straight.
+    pub fn turn_start(&self) -> f32 {
+        self.turn_start
+    }
+
+    /// Distance from which the route runs along the centre line of its outbound lane.
+    pub fn joins_exit(&self) -> f32 {
+        self.turn_start + self.radius * FRAC_PI_2
+    }
+
+    /**
+     * Where a vehicle is after driving `distance` along the route.
+     *
+     * # Returns
+     *
+     * The centre of the vehicle and its heading in radians, clockwise from
+     * east as the y axis points down the screen.
+     */
+    pub fn pose_at(&self, distance: f32) -> ((f32, f32), f32) {
+        let (e, x, r) = (self.entry, self.exit, self.radius);
+        let along = |from: (f32, f32), unit: (f32, f32), length: f32| {
+            (from.0 + unit.0 * length, from.1 + unit.1 * length)
+        };
+        if distance <= self.turn_start {
+            return (along(self.start, e, distance), e.1.atan2(e.0));
+        }
+
+        let arc = r * FRAC_PI_2;
+        let turn_start = along(self.start, e, self.turn_start);
+        if distance < self.turn_start + arc {
+            // The turn swings about a point `r` from where it starts, on the side it turns to
+            let centre = along(turn_start, x, r);
+            let angle = (distance - self.turn_start) / r;
+            let (sin, cos) = angle.sin_cos();
+            let position = (
+                centre.0 + r * (e.0 * sin - x.0 * cos),
+                centre.1 + r * (e.1 * sin - x.1 * cos),
+            );
+            let tangent = (x.0 * sin + e.0 * cos, x.1 * sin + e.1 * cos);
+            return (position, tangent.1.atan2(tangent.0));
+        }
+
+        let turn_end = along(along(turn_start, e, r), x, r);
+        (
+            along(turn_end, x, distance - self.turn_start - arc),
+            x.1.atan2(x.0),
+        )
+    }
+
+    /**
+     * Footprints of a vehicle driving the route through the box, one every
+     * `ROUTE_SAMPLE_STEP` from the front reaching the stop line to the rear
+     * leaving the box.
+     */
+    fn footprints_in_box(&self) -> Vec<Obb> {
+        let count = ((self.box_exit - self.stop_line) / ROUTE_SAMPLE_STEP).ceil() as usize;
+        (0..=count)
+            .map(|i| {
+                let (centre, angle) = self.pose_at(self.stop_line + i as f32 * ROUTE_SAMPLE_STEP);
+                Obb::square(centre, angle)
+            })
+            .collect()
+    }
+}
+
+/// Number of (movement, inbound lane) pairs a route can be driven from.
+const ROUTE_COUNT: usize = Lane::ALL.len() * MAX_LANES;
+
+/**
+ * Which routes through the box pass close enough for vehicles on them to
+ * touch, worked out once for an intersection geometry by sweeping the
+ * turned footprints of two vehicles along every pair of routes.
+ */
+#[derive(Clone, Debug)]
+pub struct RouteConflicts {
+    geometry: IntersectionGeometry,
+    /// Row-major by `route_index`, for routes on different movements.
+    meets: Vec<bool>,
+}
+
+impl RouteConflicts {
+    pub fn new(geometry: &IntersectionGeometry) -> Self {
+        let swept: Vec<(usize, Vec<Obb>)> = Lane::ALL
+            .iter()
+            .flat_map(|lane| {
+                geometry.lane_slots(lane).into_iter().map(move |slot| {
+                    let footprints = geometry.route(lane, slot).footprints_in_box();
+                    (route_index(lane, slot), footprints)
+                })
+            })
+            .collect();
+        let mut meets = vec![false; ROUTE_COUNT * ROUTE_COUNT];
+        for (i, (a, mine)) in swept.iter().enumerate() {
+            for (b, theirs) in &swept[i + 1..] {
+                // Routes of the same movement run side by side
+                if a / MAX_LANES != b / MAX_LANES && footprints_meet(mine, theirs) {
+                    meets[a * ROUTE_COUNT + b] = true;
+                    meets[b * ROUTE_COUNT + a] = true;
+                }
+            }
+        }
+        RouteConflicts {
+            geometry: *geometry,
+            meets,
+        }
+    }
+
+    /// The geometry the conflicts were worked out for.
+    pub fn geometry(&self) -> &IntersectionGeometry {
+        &self.geometry
+    }
+
+    /// Works the conflicts out again if `geometry` is not the one they are for.
+    pub fn refresh(&mut self, geometry: &IntersectionGeometry) {
+        if self.geometry != *geometry {
+            *self = Self::new(geometry);
+        }
+    }
+
+    /// Whether vehicles making movement `a` from inbound lane `slot_a` and `b`
+    /// from `slot_b` can touch in the box.
+    pub fn meet(&self, a: &Lane, slot_a: usize, b: &Lane, slot_b: usize) -> bool {
+        self.meets[route_index(a, slot_a) * ROUTE_COUNT + route_index(b, slot_b)]
+    }
+}
+
+fn route_index(lane: &Lane, slot: usize) -> usize {
+    lane.index() * MAX_LANES + slot
+}
+
+/// Whether any footprint of one sweep overlaps any of the other.
+fn footprints_meet(mine: &[Obb], theirs: &[Obb]) -> bool {
+    // Boxes whose centres are further apart than their diagonals cannot overlap
+    let reach = VEHICLE_SIZE * SQRT_2;
+    mine.iter().any(|a| {
+        theirs.iter().any(|b| {
+            let (dx, dy) = (a.centre.0 - b.centre.0, a.centre.1 - b.centre.1);
+            dx * dx + dy * dy < reach * reach && a.overlaps(b)
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::TAU;
+
+    const TOLERANCE: f32 = 1e-3;
+    const GEOMETRY: IntersectionGeometry = IntersectionGeometry::STANDARD;
+
+    /// Every movement from every lane it may use, in the standard and a four lane layout.
+    fn routes() -> Vec<(IntersectionGeometry, Lane, usize)> {
+        let four_lanes = IntersectionGeometry {
+            lanes: LaneLayout::parse("r,rs,sl,l").unwrap(),
+            ..GEOMETRY
+        };
+        [GEOMETRY, four_lanes]
+            .into_iter()
+            .flat_map(|geometry| {
+                routes_of(&geometry)
+                    .into_iter()
+                    .map(move |(lane, slot)| (geometry, lane, slot))
+            })
+            .collect()
+    }
+
+    /// Every movement and each inbound lane it may use under `geometry`.
+    fn routes_of(geometry: &IntersectionGeometry) -> Vec<(Lane, usize)> {
+        Lane::ALL
+            .into_iter()
+            .flat_map(|lane| {
+                geometry
+                    .lane_slots(&lane)
+                    .into_iter()
+                    .map(move |slot| (lane, slot))
+            })
+            .collect()
+    }
+
+    /// Smallest angle between two headings.
+    fn angle_between(a: f32, b: f32) -> f32 {
+        let difference = (a - b).rem_euclid(TAU);
+        difference.min(TAU - difference)
+    }
+
+    fn heading_of(unit: (f32, f32)) -> f32 {
+        unit.1.atan2(unit.0)
+    }
+
+    #[test]
+    fn the_standard_box_spans_three_lanes_each_way() {
+        assert_eq!((GEOMETRY.box_min(), GEOMETRY.box_max()), (295.0, 505.0));
+        assert!(GEOMETRY.overlaps_box(295.0 - VEHICLE_SIZE + 1.0, 400.0));
+        assert!(!GEOMETRY.overlaps_box(295.0 - VEHICLE_SIZE, 400.0));
+        assert!(!GEOMETRY.overlaps_box(400.0, 505.0));
+        assert!(!GEOMETRY.is_off_map(-50.0 - VEHICLE_SIZE, 850.0));
+        assert!(GEOMETRY.is_off_map(851.0, 400.0));
+    }
+
+    #[test]
+    fn right_turns_keep_to_the_edge_and_left_turns_to_the_centre_line() {
+        use Direction::*;
+
+        assert_eq!(GEOMETRY.lane_slots(&Lane::set(North, West)), [0]);
+        assert_eq!(GEOMETRY.lane_slots(&Lane::set(North, South)), [1]);
+        assert_eq!(GEOMETRY.lane_slots(&Lane::set(North, East)), [2]);
+        // Southbound traffic keeps to the west half of the road, northbound to the east
+        assert_eq!(GEOMETRY.lane_centre(North, 0), 312.5);
+        assert_eq!(GEOMETRY.lane_centre(South, 0), 487.5);
+        assert_eq!(
+            GEOMETRY.spawn_point(&Lane::set(North, South), 1),
+            (332.5, -30.0)
+        );
+        assert_eq!(
+            GEOMETRY.spawn_point(&Lane::set(West, East), 1),
+            (-30.0, 437.5)
+        );
+    }
+
+    #[test]
+    fn turns_end_a_quarter_circle_on_the_outbound_lane_centre() {
+        for (geometry, lane, slot) in routes() {
+            if lane.is_straight() {
+                continue;
+            }
+            let route = geometry.route(&lane, slot);
+            assert!(route.radius > 0.0, "{:?} has no turn", lane);
+
+            let (start, start_heading) = route.pose_at(route.turn_start);
+            let (end, end_heading) = route.pose_at(route.turn_start + route.radius * FRAC_PI_2);
+            let (e, x, r) = (route.entry, route.exit, route.radius);
+            let expected = (start.0 + r * (e.0 + x.0), start.1 + r * (e.1 + x.1));
+            assert!(
+                (end.0 - expected.0).abs() < TOLERANCE && (end.1 - expected.1).abs() < TOLERANCE,
+                "{:?} ends at {:?}, not {:?}",
+                lane,
+                end,
+                expected
+            );
+
+            let across = geometry.lane_centre(lane.to.opposite(), slot);
+            let end_across = if x.0 == 0.0 { end.0 } else { end.1 };
+            assert!((end_across - across).abs() < TOLERANCE, "{:?}", lane);
+            assert!(angle_between(start_heading, heading_of(e)) < TOLERANCE);
+            assert!(angle_between(end_heading, heading_of(x)) < TOLERANCE);
+        }
+    }
+
+    #[test]
+    fn poses_change_smoothly_along_every_route() {
+        let step = 0.5;
+        for (geometry, lane, slot) in routes() {
+            let route = geometry.route(&lane, slot);
+            let max_turn = if route.radius > 0.0 {
+                step / route.radius
+            } else {
+                0.0
+            };
+            let mut previous = route.pose_at(0.0);
+            for i in 1..=((route.box_exit + 50.0) / step) as usize {
+                let pose = route.pose_at(i as f32 * step);
+                let moved = (pose.0.0 - previous.0.0).hypot(pose.0.1 - previous.0.1);
+                let turned = angle_between(pose.1, previous.1);
+                assert!(
+                    moved <= step + TOLERANCE && turned <= max_turn + TOLERANCE,
+                    "{:?} jumps {} px and {} rad at {} px",
+                    lane,
+                    moved,
+                    turned,
+                    i as f32 * step
+                );
+                previous = pose;
+            }
+        }
+    }
+
+    #[test]
+    fn layouts_read_back_as_written() {
+        for text in ["r,s,l", "rsl", "rs,sl", "r,rs,sl,l", "rs,s,l,l"] {
+            assert_eq!(LaneLayout::parse(text).unwrap().to_string(), text);
+        }
+        assert_eq!(LaneLayout::parse("r,s,l").unwrap(), LaneLayout::STANDARD);
+        assert_eq!(LaneLayout::parse(" rs , l ").unwrap().to_string(), "rs,l");
+    }
+
+    #[test]
+    fn lanes_are_listed_in_slots_from_the_road_edge() {
+        let layout = LaneLayout::parse("rs,s,l,l").unwrap();
+        assert_eq!(layout.len(), 4);
+        assert_eq!(layout.slots_for(Turn::Right).collect::<Vec<_>>(), [0]);
+        assert_eq!(layout.slots_for(Turn::Straight).collect::<Vec<_>>(), [0, 1]);
+        assert_eq!(layout.slots_for(Turn::Left).collect::<Vec<_>>(), [2, 3]);
+
+        let geometry = IntersectionGeometry {
+            lanes: layout,
+            ..GEOMETRY
+        };
+        assert_eq!(geometry.half_road(), 140.0);
+        assert_eq!(geometry.box_min(), 260.0);
+        assert_eq!(geometry.box_max(), 540.0);
+    }
+
+    #[test]
+    fn layouts_without_a_lane_count_in_range_are_rejected() {
+        let error = LaneLayout::new(&[]).unwrap_err();
+        assert!(error.contains("1 to 4 lanes"), "{}", error);
+        let error = LaneLayout::parse("r,s,l,l,l").unwrap_err();
+        assert!(error.contains("1 to 4 lanes"), "{}", error);
+    }
+
+    #[test]
+    fn every_lane_and_every_movement_needs_a_use() {
+        for text in ["", "r,,l"] {
+            let error = LaneLayout::parse(text).unwrap_err();
+            assert!(error.contains("at least one movement"), "{}", error);
+        }
+        for (text, missing) in [("s,l", "Right"), ("r,l", "Straight"), ("r,s", "Left")] {
+            let error = LaneLayout::parse(text).unwrap_err();
+            assert!(error.contains(missing), "{}: {}", text, error);
+        }
+    }
+
+    #[test]
+    fn crossing_movements_are_rejected() {
+        for text in ["l,s,r", "s,r,l", "r,l,s", "rl,s", "r,sl,s", "rs,r,l"] {
+            let error = LaneLayout::parse(text).unwrap_err();
+            assert!(error.contains("would cross"), "{}: {}", text, error);
+        }
+    }
+
+    #[test]
+    fn lane_uses_take_each_letter_once() {
+        let error = LaneLayout::parse("r,x,l").unwrap_err();
+        assert!(error.contains("letters must be r, s or l"), "{}", error);
+        let error = LaneLayout::parse("rr,s,l").unwrap_err();
+        assert!(error.contains("given twice"), "{}", error);
+    }
+
+    #[test]
+    fn layouts_are_stored_as_a_list_of_lane_uses() {
+        let layout = LaneLayout::parse("rs,s,l,l").unwrap();
+        let json = serde_json::to_string(&layout).unwrap();
+        assert_eq!(json, r#"["rs","s","l","l"]"#);
+        assert_eq!(serde_json::from_str::<LaneLayout>(&json).unwrap(), layout);
+        assert!(serde_json::from_str::<LaneLayout>(r#"["l","r"]"#).is_err());
+    }
+
+    #[test]
+    fn narrow_lanes_leave_too_small_a_box_for_the_turns() {
+        let narrow = |lane_width: f32, text: &str| IntersectionGeometry {
+            lane_width,
+            lanes: LaneLayout::parse(text).unwrap(),
+            ..GEOMETRY
+        };
+        assert!(narrow(GEOMETRY.lane_width, "rsl").check_turns().is_ok());
+        let error = narrow(30.0, "rsl").check_turns().unwrap_err();
+        assert!(
+            error.starts_with("lanes rsl: the box is too small for "),
+            "{}",
+            error
+        );
+        assert!(narrow(25.0, "r,s,l").check_turns().is_err());
+    }
+
+    #[test]
+    fn standard_routes_meet_only_on_conflicting_movements() {
+        let routes = RouteConflicts::new(&GEOMETRY);
+        let standard = routes_of(&GEOMETRY);
+        for &(a, slot_a) in &standard {
+            for &(b, slot_b) in &standard {
+                let meet = routes.meet(&a, slot_a, &b, slot_b);
+                assert_eq!(meet, routes.meet(&b, slot_b, &a, slot_a));
+                if meet {
+                    assert!(a != b && a.conflicts_with(&b), "{:?} and {:?} meet", a, b);
+                }
+            }
+        }
+        let south = Lane::set(Direction::North, Direction::South);
+        let east = Lane::set(Direction::West, Direction::East);
+        assert!(routes.meet(&south, 1, &east, 1));
+    }
+
+    #[test]
+    fn right_turns_from_a_single_lane_meet_the_next_approach_turning_right() {
+        let geometry = IntersectionGeometry {
+            lanes: LaneLayout::parse("rsl").unwrap(),
+            ..GEOMETRY
+        };
+        let mut routes = RouteConflicts::new(&GEOMETRY);
+        routes.refresh(&geometry);
+        assert_eq!(routes.geometry(), &geometry);
+        let south_right = Lane::for_turn(Direction::South, Turn::Right);
+        let east_right = Lane::for_turn(Direction::East, Turn::Right);
+        assert!(!south_right.conflicts_with(&east_right));
+        assert!(routes.meet(&south_right, 0, &east_right, 0));
+    }
+}
@@ -0,0 +1,269 @@
+use crate::sim::{
+    Grid, Replay, RoadNetwork, Scenario, Simulation, Spawner, Statistics, TrajectoryWriter,
+    Vehicle, traffic_manager,
+};
+
+/// How long a headless run should last.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum RunLimit {
+    Ticks(u64),
+    Seconds(f32),
+}
+
+/**
+ * Runs the simulation without a display until the limit is reached.
+ *
+ * Vehicles come from `scenario` when given, otherwise from the random mode,
+ * the same way `R` does it in the window.
+ *
+ * # Arguments
+ *
+ * * `simulation` - The simulation state, carrying the seed that drives every random choice.
+ * * `limit` - How many ticks or simulated seconds to run for.
+ * * `scenario` - Spawn schedule to play back instead of random traffic.
+ * * `trajectory` - Where to stream vehicle states, if anywhere.
+ *
+ * # Returns
+ *
+ * The statistics gathered from the vehicles that completed the intersection.
+ */
+pub fn run_headless(
+    simulation: &mut Simulation,
+    limit: RunLimit,
+    scenario: Option<Scenario>,
+    trajectory: Option<&mut TrajectoryWriter>,
+) -> Statistics {
+    let mut spawner = Spawner::new();
+    match scenario {
+        Some(scenario) => spawner.load_scenario(scenario),
+        None => spawner.spawn_random = true,
+    }
+
+    run_ticks(simulation, limit, spawner, None, trajectory)
+}
+
+/**
+ * Plays a recorded session back without a display.
+ *
+ * # Arguments
+ *
+ * * `simulation` - A simulation seeded with the recording's seed.
+ * * `replay` - The recording to feed back in.
+ * * `limit` - How long to run for, the length of the recorded session when `None`.
+ * * `trajectory` - Where to stream vehicle states, if anywhere.
+ *
+ * # Returns
+ *
+ * The statistics gathered from the vehicles that completed the intersection.
+ */
+pub fn run_replay(
+    simulation: &mut Simulation,
+    mut replay: Replay,
+    limit: Option<RunLimit>,
+    trajectory: Option<&mut TrajectoryWriter>,
+) -> Statistics {
+    let mut spawner = Spawner::new();
+    if let Some(scenario) = replay.recording.scenario.clone() {
+        spawner.load_scenario(scenario);
+    }
+    let limit = limit.unwrap_or(RunLimit::Ticks(replay.recording.ticks));
+
+    run_ticks(simulation, limit, spawner, Some(&mut replay), trajectory)
+}
+
+/**
+ * Runs a grid of intersections without a display until the limit is reached,
+ * with random traffic entering at the edge of the grid.
+ *
+ * # Arguments
+ *
+ * * `simulation` - The simulation state; its policy is copied to every intersection.
+ * * `grid` - How many intersections across and down.
+ * * `limit` - How many ticks or simulated seconds to run for.
+ * * `trajectory` - Where to stream vehicle states, in network coordinates, if anywhere.
+ *
+ * # Returns
+ *
+ * The network, holding the statistics of each intersection.
+ */
+pub fn run_network(
+    simulation: &mut Simulation,
+    grid: Grid,
+    limit: RunLimit,
+    mut trajectory: Option<&mut TrajectoryWriter>,
+) -> Result<RoadNetwork, String> {
+    let mut network = RoadNetwork::new(grid, simulation)?;
+    let mut requests = Spawner::new();
+    requests.spawn_random = true;
+
+    for _ in 0..total_ticks(simulation, limit) {
+        network.step(&mut requests, simulation);
+
+        if let Some(writer) = trajectory.as_deref_mut() {
+            let placed = network.placed_vehicles(&simulation.geometry);
+            if let Err(e) = writer.record(simulation, &placed) {
+                eprintln!("{}", e);
+                trajectory = None;
+            }
+        }
+        if simulation.halted {
+            break;
+        }
+    }
+
+    if let Some(writer) = trajectory
+        && let Err(e) = writer.flush()
+    {
+        eprintln!("{}", e);
+    }
+    simulation.events.flush();
+
+    Ok(network)
+}
+
+fn total_ticks(simulation: &Simulation, limit: RunLimit) -> u64 {
+    match limit {
+        RunLimit::Ticks(ticks) => ticks,
+        RunLimit::Seconds(seconds) => (seconds.max(0.0) / simulation.dt).round() as u64,
+    }
+}
+
+fn run_ticks(
+    simulation: &mut Simulation,
+    limit: RunLimit,
+    mut spawner: Spawner,
+    mut replay: Option<&mut Replay>,
+    mut trajectory: Option<&mut TrajectoryWriter>,
+) -> Statistics {
+    let mut vehicles: Vec<Vehicle> = Vec::new();
+    let mut completed_vehicles: Vec<Vehicle> = Vec::new();
+    let mut statistics = Statistics::new();
+
+    for _ in 0..total_ticks(simulation, limit) {
+        if let Some(replay) = replay.as_deref_mut() {
+            replay.apply_due(simulation.tick, &mut spawner);
+        }
+        traffic_manager(
+            &mut spawner,
+            &mut vehicles,
+            &mut statistics,
+            &mut completed_vehicles,
+            simulation,
+        );
+        spawner.reset();
+
+        if let Some(writer) = trajectory.as_deref_mut()
+            && let Err(e) = writer.record(simulation, &vehicles)
+        {
+            eprintln!("{}", e);
+            trajectory = None;
+        }
+        if simulation.halted {
+            break;
+        }
+    }
+
+    if let Some(writer) = trajectory
+        && let Err(e) = writer.flush()
+    {
+        eprintln!("{}", e);
+    }
+    simulation.events.flush();
+
+    statistics
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn vehicles_cross_without_a_display() {
+        let statistics = run_headless(
+            &mut Simulation::with_seed(1),
+            RunLimit::Seconds(60.0),
+            None,
+            None,
+        );
+        assert!(statistics.max_vehicles_passed > 0);
+        assert!(statistics.min_velocity <= statistics.max_velocity);
+    }
+
+    #[test]
+    fn nothing_happens_in_zero_ticks() {
+        let statistics = run_headless(
+            &mut Simulation::with_seed(1),
+            RunLimit::Ticks(0),
+            None,
+            None,
+        );
+        assert_eq!(statistics.max_vehicles_passed, 0);
+    }
+
+    #[test]
+    fn a_seed_reproduces_the_run() {
+        let run = |seed| {
+            run_headless(
+                &mut Simulation::with_seed(seed),
+                RunLimit::Seconds(60.0),
+                None,
+                None,
+            )
+            .summary_lines()
+        };
+        assert_eq!(run(7), run(7));
+    }
+
+    #[test]
+    fn seconds_are_counted_in_whole_ticks() {
+        let mut simulation = Simulation::with_seed(1);
+        run_headless(&mut simulation, RunLimit::Seconds(1.5), None, None);
+        assert_eq!(simulation.tick, 90);
+        run_headless(&mut simulation, RunLimit::Ticks(10), None, None);
+        assert_eq!(simulation.tick, 100);
+    }
+
+    #[test]
+    fn the_series_is_sampled_once_per_simulated_second() {
+        let statistics = run_headless(
+            &mut Simulation::with_seed(1),
+            RunLimit::Seconds(3.5),
+            None,
+            None,
+        );
+        let times: Vec<f32> = statistics.series.samples().map(|s| s.time).collect();
+        assert_eq!(times, [1.0, 2.0, 3.0]);
+    }
+
+    #[test]
+    fn a_scenario_replaces_random_traffic() {
+        let scenario: Scenario =
+            toml::from_str("[[spawns]]\ntime = 1.0\nfrom = \"west\"\nto = \"east\"").unwrap();
+        let mut simulation = Simulation::with_seed(1);
+        let statistics = run_headless(
+            &mut simulation,
+            RunLimit::Seconds(30.0),
+            Some(scenario),
+            None,
+        );
+        assert_eq!(statistics.max_vehicles_passed, 1);
+        assert_eq!(simulation.next_vehicle_id, 1);
+    }
+
+    #[test]
+    fn vehicles_cross_a_network_and_leave_it() {
+        let mut simulation = Simulation::with_seed(1);
+        let network = run_network(
+            &mut simulation,
+            Grid::parse("2x1").unwrap(),
+            RunLimit::Seconds(30.0),
+            None,
+        )
+        .unwrap();
+        assert!(network.vehicles_left() > 0);
+        // Vehicles can only reach the shared edge from the other intersection
+        for junction in &network.junctions {
+            assert!(!junction.statistics.journeys.is_empty());
+        }
+    }
+}
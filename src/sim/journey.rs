@@ -0,0 +1,195 @@
+use crate::sim::{CRUISE_SPEED, Lane, Vehicle};
+use serde::Serialize;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+/**
+ * The trip of one vehicle that left the screen. Times are simulation times
+ * in seconds, durations simulated seconds.
+ */
+#[derive(Clone, Debug, Serialize)]
+pub struct Journey {
+    pub id: usize,
+    pub lane: Lane,
+    pub spawn_time: f32,
+    /// When the vehicle first reached its stop line.
+    pub stop_line_time: Option<f32>,
+    /// Time spent stopped in `VehicleState::Waiting`.
+    pub waiting_time: f32,
+    /// Time spent inside the intersection box.
+    pub box_time: Option<f32>,
+    pub exit_time: f32,
+    /// Time from spawn to leaving the screen.
+    pub travel_time: f32,
+    /// Time the same route takes at cruising speed.
+    pub free_flow_time: f32,
+    /// `travel_time - free_flow_time`.
+    pub delay: f32,
+    /// Distance driven over travel time (px/s).
+    pub average_speed: f32,
+    pub close_call: bool,
+}
+
+impl Journey {
+    /// The journey of a vehicle that has just left the screen at simulation time `now`.
+    pub fn from_vehicle(vehicle: &Vehicle, now: f32) -> Self {
+        let travel_time = now - vehicle.spawn_time;
+        let free_flow_time = vehicle.distance_travelled / CRUISE_SPEED;
+        Journey {
+            id: vehicle.id,
+            lane: vehicle.lane,
+            spawn_time: vehicle.spawn_time,
+            stop_line_time: vehicle.stop_line_time,
+            waiting_time: vehicle.waiting_time,
+            box_time: vehicle
+                .box_enter_time
+                .zip(vehicle.box_exit_time)
+                .map(|(enter, exit)| exit - enter),
+            exit_time: now,
+            travel_time,
+            free_flow_time,
+            delay: travel_time - free_flow_time,
+            average_speed: vehicle.distance_travelled / travel_time.max(f32::EPSILON),
+            close_call: vehicle.had_close_call,
+        }
+    }
+}
+
+/**
+ * Writes one line per journey to `path`: CSV, or JSON Lines when the file
+ * name ends in `.jsonl` or `.json`.
+ *
+ * # Returns
+ *
+ * A Result with an error string if the file could not be written.
+ */
+pub fn export_journeys(path: &str, journeys: &[Journey]) -> Result<(), String> {
+    let is_json = Path::new(path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| e == "jsonl" || e == "json");
+    write_journeys(path, journeys, is_json)
+        .map_err(|e| format!("cannot write journeys {}: {}", path, e))
+}
+
+fn write_journeys(path: &str, journeys: &[Journey], is_json: bool) -> std::io::Result<()> {
+    let mut out = BufWriter::new(File::create(path)?);
+    if !is_json {
+        writeln!(
+            out,
+            "id,from,to,spawn_time,stop_line_time,waiting_time,box_time,exit_time,\
+             travel_time,free_flow_time,delay,average_speed,close_call"
+        )?;
+    }
+
+    let optional = |value: Option<f32>| value.map(|v| v.to_string()).unwrap_or_default();
+    for journey in journeys {
+        if is_json {
+            serde_json::to_writer(&mut out, journey)?;
+            writeln!(out)?;
+            continue;
+        }
+        writeln!(
+            out,
+            "{},{},{},{},{},{},{},{},{},{},{},{},{}",
+            journey.id,
+            format!("{:?}", journey.lane.from).to_lowercase(),
+            format!("{:?}", journey.lane.to).to_lowercase(),
+            journey.spawn_time,
+            optional(journey.stop_line_time),
+            journey.waiting_time,
+            optional(journey.box_time),
+            journey.exit_time,
+            journey.travel_time,
+            journey.free_flow_time,
+            journey.delay,
+            journey.average_speed,
+            journey.close_call,
+        )?;
+    }
+    out.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sim::IntersectionGeometry;
+    use crate::sim::types::Direction;
+    use rand::{SeedableRng, rngs::StdRng};
+
+    /// A South -> North vehicle spawned at 2 s that drove 600 px.
+    fn journey(exit_time: f32) -> Journey {
+        let mut rng = StdRng::seed_from_u64(0);
+        let lane = Lane::set(Direction::South, Direction::North);
+        let geometry = IntersectionGeometry::STANDARD;
+        let slot = geometry.lane_slots(&lane)[0];
+        let mut vehicle = Vehicle::new(4, lane, slot, &geometry, 2.0, &mut rng);
+        vehicle.distance_travelled = 600.0;
+        vehicle.waiting_time = 1.5;
+        vehicle.stop_line_time = Some(6.0);
+        vehicle.box_enter_time = Some(7.5);
+        vehicle.box_exit_time = Some(10.0);
+        Journey::from_vehicle(&vehicle, exit_time)
+    }
+
+    /// A fresh file name in the temporary directory.
+    fn temp_path(name: &str) -> String {
+        let path = std::env::temp_dir().join(format!("smart-road-{}-{}", std::process::id(), name));
+        path.to_str().unwrap().to_string()
+    }
+
+    #[test]
+    fn delay_is_the_travel_time_beyond_free_flow() {
+        let journey = journey(20.0);
+        assert_eq!(journey.spawn_time, 2.0);
+        assert_eq!(journey.travel_time, 18.0);
+        assert_eq!(journey.box_time, Some(2.5));
+        assert_eq!(journey.free_flow_time, 600.0 / CRUISE_SPEED);
+        assert_eq!(journey.delay, 18.0 - 600.0 / CRUISE_SPEED);
+        assert_eq!(journey.average_speed, 600.0 / 18.0);
+    }
+
+    #[test]
+    fn a_vehicle_that_never_left_the_box_has_no_box_time() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let lane = Lane::set(Direction::South, Direction::North);
+        let geometry = IntersectionGeometry::STANDARD;
+        let slot = geometry.lane_slots(&lane)[0];
+        let mut vehicle = Vehicle::new(4, lane, slot, &geometry, 0.0, &mut rng);
+        vehicle.box_enter_time = Some(7.5);
+        assert_eq!(Journey::from_vehicle(&vehicle, 9.0).box_time, None);
+    }
+
+    #[test]
+    fn csv_exports_leave_missing_times_empty() {
+        let path = temp_path("journeys.csv");
+        let mut unfinished = journey(20.0);
+        unfinished.stop_line_time = None;
+        unfinished.box_time = None;
+        export_journeys(&path, &[journey(20.0), unfinished]).unwrap();
+        let text = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("id,from,to,spawn_time,stop_line_time,"));
+        assert!(lines[1].starts_with("4,south,north,2,6,1.5,2.5,20,18,"));
+        assert!(lines[2].starts_with("4,south,north,2,,1.5,,20,18,"));
+    }
+
+    #[test]
+    fn json_lines_exports_write_one_object_per_journey() {
+        let path = temp_path("journeys.jsonl");
+        export_journeys(&path, &[journey(20.0), journey(21.0)]).unwrap();
+        let text = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let lines: Vec<serde_json::Value> = text
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["lane"]["from"], "south");
+        assert_eq!(lines[0]["waiting_time"], 1.5);
+        assert_eq!(lines[1]["exit_time"], 21.0);
+    }
+}
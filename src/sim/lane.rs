@@ -0,0 +1,197 @@
+use crate::sim::types::Direction;
+use serde::{Deserialize, Serialize};
+
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Serialize, Deserialize)]
+pub struct Lane {
+    pub from: Direction,
+    pub to: Direction,
+}
+
+/**
+ * Which movements cross paths inside the intersection box, in `Lane::ALL` order.
+ *
+ * Row `i`, column `j` is `X` when the areas swept by lanes `i` and `j` through
+ * the box overlap. Right turns stay in their corner and conflict with nothing
+ * but themselves; opposing straight movements never meet.
+ */
+const CONFLICTS: [&[u8; 12]; 12] = [
+    //  N: r s l  E: r s l  S: r s l  W: r s l
+    b"X...........", // North -> West  (r)
+    b".X..X...X.XX", // North -> South (s)
+    b"..X.XX.XX..X", // North -> East  (l)
+    b"...X........", // East  -> North (r)
+    b".XX.X..X...X", // East  -> West  (s)
+    b"..X..X.XX.XX", // East  -> South (l)
+    b"......X.....", // South -> East  (r)
+    b"..X.XX.X..X.", // South -> North (s)
+    b".XX..X..X.XX", // South -> West  (l)
+    b".........X..", // West  -> South (r)
+    b".X...X.XX.X.", // West  -> East  (s)
+    b".XX.XX..X..X", // West  -> North (l)
+];
+
+/// The kind of movement a lane makes through the box, in order from the road edge.
+#[derive(Copy, Clone, Eq, PartialEq, PartialOrd, Ord, Hash, Debug)]
+pub enum Turn {
+    Right,
+    Straight,
+    Left,
+}
+
+impl Turn {
+    pub const ALL: [Turn; 3] = [Turn::Right, Turn::Straight, Turn::Left];
+
+    /// The letter marking the movement on the road: r, s or l.
+    pub fn letter(&self) -> char {
+        match self {
+            Turn::Right => 'r',
+            Turn::Straight => 's',
+            Turn::Left => 'l',
+        }
+    }
+
+    pub fn from_letter(letter: char) -> Option<Turn> {
+        Turn::ALL.into_iter().find(|turn| turn.letter() == letter)
+    }
+}
+
+impl Lane {
+    /// Every movement through the intersection: right, straight and left for each approach.
+    pub const ALL: [Lane; 12] = [
+        Lane::set(Direction::North, Direction::West),
+        Lane::set(Direction::North, Direction::South),
+        Lane::set(Direction::North, Direction::East),
+        Lane::set(Direction::East, Direction::North),
+        Lane::set(Direction::East, Direction::West),
+        Lane::set(Direction::East, Direction::South),
+        Lane::set(Direction::South, Direction::East),
+        Lane::set(Direction::South, Direction::North),
+        Lane::set(Direction::South, Direction::West),
+        Lane::set(Direction::West, Direction::South),
+        Lane::set(Direction::West, Direction::East),
+        Lane::set(Direction::West, Direction::North),
+    ];
+
+    pub const fn set(from: Direction, to: Direction) -> Self {
+        Lane { from, to }
+    }
+
+    /// The movement making `turn` from approach `from`.
+    pub fn for_turn(from: Direction, turn: Turn) -> Self {
+        *Lane::ALL
+            .iter()
+            .find(|lane| lane.from == from && lane.turn() == turn)
+            .expect("every approach has a right, straight and left movement")
+    }
+
+    /// Position of the lane in `Lane::ALL`.
+    pub fn index(&self) -> usize {
+        Lane::ALL
+            .iter()
+            .position(|lane| lane == self)
+            .expect("lane must join two different approaches")
+    }
+
+    pub fn is_right_turn(&self) -> bool {
+        match self.from {
+            Direction::North => self.to == Direction::West,
+            Direction::South => self.to == Direction::East,
+            Direction::East => self.to == Direction::North,
+            Direction::West => self.to == Direction::South,
+        }
+    }
+
+    pub fn is_straight(&self) -> bool {
+        self.to == self.from.opposite()
+    }
+
+    pub fn is_left_turn(&self) -> bool {
+        !self.is_right_turn() && !self.is_straight()
+    }
+
+    pub fn turn(&self) -> Turn {
+        if self.is_right_turn() {
+            Turn::Right
+        } else if self.is_straight() {
+            Turn::Straight
+        } else {
+            Turn::Left
+        }
+    }
+
+    /// Whether vehicles on the two lanes can meet inside the intersection box.
+    pub fn conflicts_with(&self, other: &Lane) -> bool {
+        CONFLICTS[self.index()][other.index()] == b'X'
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn conflicts_are_symmetric_and_include_the_lane_itself() {
+        for a in Lane::ALL {
+            assert!(a.conflicts_with(&a), "{:?}", a);
+            for b in Lane::ALL {
+                assert_eq!(
+                    a.conflicts_with(&b),
+                    b.conflicts_with(&a),
+                    "{:?} {:?}",
+                    a,
+                    b
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn right_turns_conflict_with_nothing_else() {
+        for right in Lane::ALL.iter().filter(|lane| lane.is_right_turn()) {
+            let conflicts: Vec<_> = Lane::ALL
+                .iter()
+                .filter(|other| right.conflicts_with(other))
+                .collect();
+            assert_eq!(conflicts, [right]);
+        }
+    }
+
+    #[test]
+    fn straight_movements_cross_only_their_perpendiculars() {
+        use Direction::*;
+
+        let south = Lane::set(North, South);
+        assert!(!south.conflicts_with(&Lane::set(South, North)));
+        assert!(south.conflicts_with(&Lane::set(East, West)));
+        assert!(south.conflicts_with(&Lane::set(West, East)));
+        // The left turn from the opposite approach cuts across it
+        assert!(south.conflicts_with(&Lane::set(South, West)));
+    }
+
+    #[test]
+    fn index_follows_the_order_of_all() {
+        for (i, lane) in Lane::ALL.iter().enumerate() {
+            assert_eq!(lane.index(), i);
+        }
+    }
+
+    #[test]
+    fn every_movement_has_its_turn_and_letter() {
+        use Direction::*;
+
+        assert_eq!(Lane::set(North, West).turn(), Turn::Right);
+        assert_eq!(Lane::set(North, South).turn(), Turn::Straight);
+        assert_eq!(Lane::set(North, East).turn(), Turn::Left);
+        for turn in Turn::ALL {
+            assert_eq!(Turn::from_letter(turn.letter()), Some(turn));
+        }
+        assert_eq!(Turn::from_letter('x'), None);
+    }
+
+    #[test]
+    fn each_approach_has_one_movement_per_turn() {
+        for lane in Lane::ALL {
+            assert_eq!(Lane::for_turn(lane.from, lane.turn()), lane);
+        }
+    }
+}
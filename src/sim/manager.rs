@@ -0,0 +1,204 @@
+use crate::sim::{
+    Collision, CollisionRecord, Conflict, Journey, SimEvent, Simulation, Spawner, Statistics,
+    TICKS_PER_SECOND, World, vehicle,
+};
+
+pub fn traffic_manager(
+    spawner: &mut Spawner,
+    vehicles: &mut Vec<vehicle::Vehicle>,
+    statistics: &mut Statistics,
+    completed_vehicles: &mut Vec<vehicle::Vehicle>,
+    simulation: &mut Simulation,
+) {
+    step_intersection(
+        spawner,
+        vehicles,
+        statistics,
+        completed_vehicles,
+        simulation,
+    );
+    check_safety(vehicles, statistics, simulation);
+
+    simulation.advance_clock();
+    sample_if_due(statistics, vehicles, simulation);
+}
+
+/**
+ * Runs one tick of a single intersection, without moving the clock: spawns,
+ * admits and moves its vehicles and removes those that left the map. The
+ * collisions and close calls are left to `check_safety`, run once every
+ * vehicle has moved.
+ *
+ * # Arguments
+ *
+ * * `spawner` - Spawn requests for this tick.
+ * * `vehicles` - The vehicles on the intersection's map.
+ * * `statistics` - Where the intersection's journeys go.
+ * * `completed_vehicles` - The vehicles that left the map so far.
+ * * `simulation` - Clock, policy and events of the intersection.
+ */
+pub fn step_intersection(
+    spawner: &mut Spawner,
+    vehicles: &mut Vec<vehicle::Vehicle>,
+    statistics: &mut Statistics,
+    completed_vehicles: &mut Vec<vehicle::Vehicle>,
+    simulation: &mut Simulation,
+) {
+    spawner.spawn_cars(vehicles, simulation);
+
+    // Track vehicles to remove
+    let mut vehicles_to_remove: Vec<usize> = Vec::new();
+
+    let now = simulation.time();
+    let dt = simulation.dt;
+    simulation.route_conflicts.refresh(&simulation.geometry);
+    simulation.policy.begin_tick(&World {
+        vehicles,
+        routes: &simulation.route_conflicts,
+        now,
+        dt,
+    });
+
+    // update vehicles
+    for i in 0..vehicles.len() {
+        // Create a temporary reference to avoid borrowing issues
+        let vehicles_clone = vehicles.clone();
+        let world = World {
+            vehicles: &vehicles_clone,
+            routes: &simulation.route_conflicts,
+            now,
+            dt,
+        };
+        vehicles[i].admission = simulation.policy.admit(&vehicles[i], &world);
+        let state_before = vehicles[i].state;
+        let was_in_box = vehicles[i].box_enter_time.is_some();
+        vehicles[i].update(&vehicles_clone, now, dt);
+
+        let (id, lane) = (vehicles[i].id, vehicles[i].lane);
+        if vehicles[i].state != state_before {
+            simulation.emit(SimEvent::StateChanged {
+                id,
+                from: state_before,
+                to: vehicles[i].state,
+            });
+        }
+        if !was_in_box && vehicles[i].box_enter_time.is_some() {
+            simulation.emit(SimEvent::EnteredIntersection { id, lane });
+        }
+
+        // Remove vehicles that have exited screen (completed intersection)
+        if vehicles[i]
+            .geometry
+            .is_off_map(vehicles[i].x, vehicles[i].y)
+        {
+            record_exit(&mut vehicles[i], statistics, completed_vehicles, simulation);
+            vehicles_to_remove.push(i);
+        }
+    }
+
+    // Remove completed vehicles (in reverse order to maintain indices)
+    for &index in vehicles_to_remove.iter().rev() {
+        vehicles.remove(index);
+    }
+
+    // Update statistics with completed vehicles
+    statistics.breakdowns.sample_queues(vehicles);
+    statistics.calculate_intersection_stats(completed_vehicles);
+}
+
+/// Looks for collisions and close calls among the vehicles of a single intersection, as of the end of the step.
+pub fn check_safety(
+    vehicles: &mut [vehicle::Vehicle],
+    statistics: &mut Statistics,
+    simulation: &mut Simulation,
+) {
+    let end_of_step = simulation.time() + simulation.dt;
+    for (a, b) in Collision::overlapping_pairs(vehicles) {
+        let record = CollisionRecord::new(end_of_step, &vehicles[a], &vehicles[b]);
+        record_collision(record, statistics, simulation);
+    }
+
+    for conflict in simulation.safety.observe(vehicles, end_of_step) {
+        for vehicle in vehicles
+            .iter_mut()
+            .filter(|v| conflict.vehicles.contains(&v.id))
+        {
+            vehicle.had_close_call = true;
+        }
+        record_close_call(conflict, statistics, simulation);
+    }
+}
+
+/// Books a collision unless the pair collided before, halting the run if that was asked for.
+pub fn record_collision(
+    record: CollisionRecord,
+    statistics: &mut Statistics,
+    simulation: &mut Simulation,
+) {
+    let event = SimEvent::Collision {
+        id: record.vehicles[0],
+        other: record.vehicles[1],
+        x: record.position.0,
+        y: record.position.1,
+    };
+    if statistics.record_collision(record) {
+        simulation.emit(event);
+        if simulation.halt_on_collision && !simulation.halted {
+            simulation.halted = true;
+            simulation.paused = true;
+        }
+    }
+}
+
+/// Books a close call and announces it.
+pub fn record_close_call(
+    conflict: Conflict,
+    statistics: &mut Statistics,
+    simulation: &mut Simulation,
+) {
+    simulation.emit(SimEvent::CloseCall {
+        id: conflict.vehicles[0],
+        other: conflict.vehicles[1],
+        measure: conflict.measure,
+        value: conflict.value,
+    });
+    statistics.record_conflict(conflict);
+}
+
+/**
+ * Books a vehicle leaving the intersection's map: its exit event, journey and
+ * completion. It left during the step now running, so the exit is stamped
+ * with the end of that step, like the other times the step records.
+ */
+pub fn record_exit(
+    vehicle: &mut vehicle::Vehicle,
+    statistics: &mut Statistics,
+    completed_vehicles: &mut Vec<vehicle::Vehicle>,
+    simulation: &mut Simulation,
+) {
+    let exit_time = simulation.time() + simulation.dt;
+    let travel_time = exit_time - vehicle.spawn_time;
+    vehicle.intersection_exit_time = Some(travel_time);
+    simulation.emit(SimEvent::Exited {
+        id: vehicle.id,
+        lane: vehicle.lane,
+        travel_time,
+    });
+    statistics
+        .journeys
+        .push(Journey::from_vehicle(vehicle, exit_time));
+    completed_vehicles.push(vehicle.clone());
+}
+
+/// Samples the state at the end of every simulated second, once the clock has moved on.
+pub fn sample_if_due(
+    statistics: &mut Statistics,
+    vehicles: &[vehicle::Vehicle],
+    simulation: &Simulation,
+) {
+    // Timed from the tick count so whole seconds stay exact
+    if simulation.tick.is_multiple_of(TICKS_PER_SECOND) {
+        let second = (simulation.tick / TICKS_PER_SECOND) as f32;
+        statistics.record_sample(vehicles, second);
+    }
+}
@@ -0,0 +1,71 @@
+pub mod types;
+pub use types::*;
+
+pub mod events;
+pub use events::*;
+
+pub mod simulation;
+pub use simulation::*;
+
+pub mod lane;
+pub use lane::*;
+
+pub mod geometry;
+pub use geometry::*;
+
+pub mod collision;
+pub use collision::*;
+
+pub mod safety;
+pub use safety::*;
+
+pub mod vehicle;
+pub use vehicle::*;
+
+pub mod reservation;
+pub use reservation::*;
+
+pub mod traffic_light;
+pub use traffic_light::*;
+
+pub mod policy;
+pub use policy::*;
+
+pub mod scenario;
+pub use scenario::*;
+
+pub mod replay;
+pub use replay::*;
+
+pub mod spawner;
+pub use spawner::*;
+
+pub mod manager;
+pub use manager::*;
+
+pub mod network;
+pub use network::*;
+
+pub mod distribution;
+pub use distribution::*;
+
+pub mod journey;
+pub use journey::*;
+
+pub mod breakdown;
+pub use breakdown::*;
+
+pub mod series;
+pub use series::*;
+
+pub mod statistics;
+pub use statistics::*;
+
+pub mod report;
+pub use report::*;
+
+pub mod trajectory;
+pub use trajectory::*;
+
+pub mod headless;
+pub use headless::*;
@@ -0,0 +1,652 @@
+use crate::sim::types::Direction;
+use crate::sim::{
+    Collision, CollisionRecord, InputAction, IntersectionGeometry, IntersectionPolicy, Lane,
+    LiveStats, SimEvent, Simulation, Spawner, Statistics, Turn, VEHICLE_SIZE, Vehicle,
+    policy_from_name, record_close_call, record_collision, record_exit, sample_if_due,
+    step_intersection,
+};
+use rand::Rng;
+use serde::Serialize;
+use std::fmt;
+
+/// Most intersections along either side of a grid.
+pub const MAX_GRID_SIDE: usize = 8;
+
+/// Window for the live throughput figure (simulated seconds), as for a single intersection.
+const THROUGHPUT_WINDOW: f32 = 60.0;
+
+/// The four sides of an intersection, in the order their approaches are listed.
+const SIDES: [Direction; 4] = [
+    Direction::North,
+    Direction::East,
+    Direction::South,
+    Direction::West,
+];
+
+/// Size of a road network in intersections across and down, written `3x2`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Grid {
+    pub columns: usize,
+    pub rows: usize,
+}
+
+impl Grid {
+    /// Parses `COLUMNSxROWS`, each side from 1 to `MAX_GRID_SIDE`.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let (columns, rows) = text
+            .split_once('x')
+            .ok_or_else(|| format!("expected COLUMNSxROWS, got {}", text))?;
+        let side = |value: &str| {
+            value
+                .trim()
+                .parse::<usize>()
+                .ok()
+                .filter(|count| (1..=MAX_GRID_SIDE).contains(count))
+                .ok_or_else(|| {
+                    format!(
+                        "{} is not a number of intersections from 1 to {}",
+                        value, MAX_GRID_SIDE
+                    )
+                })
+        };
+        Ok(Grid {
+            columns: side(columns)?,
+            rows: side(rows)?,
+        })
+    }
+
+    /// Number of intersections in the grid.
+    pub fn count(&self) -> usize {
+        self.columns * self.rows
+    }
+
+    /// Index of the intersection next to intersection `index` on `side`, `None` at the edge of the grid.
+    pub fn neighbour(&self, index: usize, side: Direction) -> Option<usize> {
+        let (column, row) = (index % self.columns, index / self.columns);
+        match side {
+            Direction::North => row.checked_sub(1).map(|row| row * self.columns + column),
+            Direction::South => (row + 1 < self.rows).then(|| index + self.columns),
+            Direction::West => column.checked_sub(1).map(|_| index - 1),
+            Direction::East => (column + 1 < self.columns).then(|| index + 1),
+        }
+    }
+}
+
+impl fmt::Display for Grid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}x{}", self.columns, self.rows)
+    }
+}
+
+/// One intersection of a road network, with its own controller and records.
+pub struct Junction {
+    pub column: usize,
+    pub row: usize,
+    /// Decides which vehicles may enter this intersection's box.
+    pub policy: Box<dyn IntersectionPolicy>,
+    /// Spawn requests for the approaches of this intersection on the edge of the grid.
+    pub spawner: Spawner,
+    /// The vehicles on this intersection's map, in its own coordinates.
+    pub vehicles: Vec<Vehicle>,
+    pub completed_vehicles: Vec<Vehicle>,
+    /// Passes through this intersection; a vehicle handed on counts as leaving it.
+    pub statistics: Statistics,
+}
+
+impl Junction {
+    /// Top-left corner of the intersection's map in network coordinates.
+    pub fn origin(&self, geometry: &IntersectionGeometry) -> (f32, f32) {
+        (
+            self.column as f32 * geometry.size,
+            self.row as f32 * geometry.size,
+        )
+    }
+}
+
+/**
+ * A grid of intersections joined by road segments.
+ *
+ * Every intersection is laid out by the simulation's geometry and the maps
+ * of neighbours share an edge, so the road segment between two boxes is the
+ * exit arm of one followed by the approach arm of the next. A vehicle whose
+ * front reaches a shared edge is handed off to the next intersection in the
+ * same lane slot, taking one of the movements that lane allows; at the edge
+ * of the grid it leaves the network. New vehicles only enter on approaches
+ * at the edge of the grid.
+ */
+pub struct RoadNetwork {
+    pub grid: Grid,
+    /// The intersections row by row, top-left first.
+    pub junctions: Vec<Junction>,
+    /// Simulation times (s) at which vehicles left the network, in order.
+    exit_times: Vec<f32>,
+}
+
+impl RoadNetwork {
+    /**
+     * Creates a network of `grid` intersections, each with its own controller
+     * of the kind `simulation` runs.
+     *
+     * # Returns
+     *
+     * A Result with an error string if the policy cannot be created.
+     */
+    pub fn new(grid: Grid, simulation: &Simulation) -> Result<Self, String> {
+        let mut junctions = Vec::with_capacity(grid.count());
+        for index in 0..grid.count() {
+            junctions.push(Junction {
+                column: index % grid.columns,
+                row: index / grid.columns,
+                policy: policy_from_name(simulation.policy.name(), &simulation.signals)?,
+                spawner: Spawner::new(),
+                vehicles: Vec::new(),
+                completed_vehicles: Vec::new(),
+                statistics: Statistics::new(),
+            });
+        }
+        Ok(RoadNetwork {
+            grid,
+            junctions,
+            exit_times: Vec::new(),
+        })
+    }
+
+    /// Width and height of the whole network (px).
+    pub fn size(&self, geometry: &IntersectionGeometry) -> (f32, f32) {
+        (
+            self.grid.columns as f32 * geometry.size,
+            self.grid.rows as f32 * geometry.size,
+        )
+    }
+
+    /// The approaches vehicles enter the network by: intersection index and side, in grid order.
+    pub fn entries(&self) -> Vec<(usize, Direction)> {
+        (0..self.grid.count())
+            .flat_map(|index| SIDES.map(|side| (index, side)))
+            .filter(|&(index, side)| self.grid.neighbour(index, side).is_none())
+            .collect()
+    }
+
+    /// Vehicles that left the network at the edge of the grid.
+    pub fn vehicles_left(&self) -> usize {
+        self.exit_times.len()
+    }
+
+    /// Vehicles on the network right now.
+    pub fn vehicle_count(&self) -> usize {
+        self.junctions.iter().map(|j| j.vehicles.len()).sum()
+    }
+
+    /**
+     * Runs one tick of the whole network and moves the clock on.
+     *
+     * Spawn requests raised on `requests` (keyboard, replay or random mode)
+     * are passed to an intersection on the edge of the grid. Each
+     * intersection then steps with its own controller in place of the
+     * simulation's, and vehicles that reached a shared edge move on to the
+     * next intersection. Collisions and close calls are looked for last, over
+     * the whole network, so pairs on either side of a shared edge count too.
+     */
+    pub fn step(&mut self, requests: &mut Spawner, simulation: &mut Simulation) {
+        self.dispatch(requests, simulation);
+        self.follow_across_edges();
+
+        let end_of_step = simulation.time() + simulation.dt;
+        for junction in &mut self.junctions {
+            std::mem::swap(&mut simulation.policy, &mut junction.policy);
+            let completed_before = junction.completed_vehicles.len();
+            step_intersection(
+                &mut junction.spawner,
+                &mut junction.vehicles,
+                &mut junction.statistics,
+                &mut junction.completed_vehicles,
+                simulation,
+            );
+            std::mem::swap(&mut simulation.policy, &mut junction.policy);
+            junction.spawner.reset();
+
+            // Hand-offs come after every step, so whatever left this map left the network
+            let left = junction.completed_vehicles.len() - completed_before;
+            self.exit_times
+                .extend(std::iter::repeat_n(end_of_step, left));
+        }
+        self.hand_off(simulation);
+        self.check_safety(simulation);
+
+        simulation.advance_clock();
+        for junction in &mut self.junctions {
+            sample_if_due(&mut junction.statistics, &junction.vehicles, simulation);
+        }
+    }
+
+    /// Passes spawn requests on to the intersections whose approaches are on the edge of the grid.
+    fn dispatch(&mut self, requests: &mut Spawner, simulation: &mut Simulation) {
+        let entries = self.entries();
+
+        // The random mode keeps each entry as busy as an approach of a single intersection
+        if requests.spawn_random {
+            let now = simulation.time();
+            let interval = requests.random_interval_ms as f32 / 1000.0 * SIDES.len() as f32
+                / entries.len() as f32;
+            if now - requests.random_last >= interval {
+                let (index, from) = entries[simulation.rng.gen_range(0..entries.len())];
+                InputAction::Spawn { from }.apply(&mut self.junctions[index].spawner);
+                requests.random_last = now;
+            }
+        }
+
+        // An arrow key spawns on a random intersection along that edge of the grid
+        for (requested, from) in [
+            (requests.spawn_south, Direction::South),
+            (requests.spawn_north, Direction::North),
+            (requests.spawn_east, Direction::East),
+            (requests.spawn_west, Direction::West),
+        ] {
+            if requested {
+                let candidates: Vec<usize> = entries
+                    .iter()
+                    .filter(|(_, side)| *side == from)
+                    .map(|(index, _)| *index)
+                    .collect();
+                let index = candidates[simulation.rng.gen_range(0..candidates.len())];
+                InputAction::Spawn { from }.apply(&mut self.junctions[index].spawner);
+            }
+        }
+    }
+
+    /**
+     * Lets vehicles driving towards a shared edge see the last vehicle in
+     * their lane on the other side of it, so queues back up across the edge
+     * rather than through each other.
+     */
+    fn follow_across_edges(&mut self) {
+        let mut leaders: Vec<(usize, usize, Option<f32>)> = Vec::new();
+        for (index, junction) in self.junctions.iter().enumerate() {
+            for (position, vehicle) in junction.vehicles.iter().enumerate() {
+                let Some(next) = self.grid.neighbour(index, vehicle.lane.to) else {
+                    continue;
+                };
+                let from = vehicle.lane.to.opposite();
+                let leader = self.junctions[next]
+                    .vehicles
+                    .iter()
+                    .filter(|other| {
+                        other.lane.from == from
+                            && other.slot == vehicle.slot
+                            && other.distance_travelled
+                                < other.route.straight_until() + VEHICLE_SIZE
+                    })
+                    .map(|other| other.distance_travelled)
+                    .reduce(f32::min);
+                leaders.push((
+                    index,
+                    position,
+                    leader.map(|distance| vehicle.route.map_exit + distance),
+                ));
+            }
+        }
+        for (index, position, leader) in leaders {
+            self.junctions[index].vehicles[position].leader_beyond_map = leader;
+        }
+    }
+
+    /// Moves the vehicles whose front reached a shared edge on to the next intersection.
+    fn hand_off(&mut self, simulation: &mut Simulation) {
+        let geometry = simulation.geometry;
+        let mut arrivals: Vec<(usize, Vehicle)> = Vec::new();
+        for (index, junction) in self.junctions.iter_mut().enumerate() {
+            let mut position = 0;
+            while position < junction.vehicles.len() {
+                let vehicle = &junction.vehicles[position];
+                let next = self
+                    .grid
+                    .neighbour(index, vehicle.lane.to)
+                    .filter(|_| vehicle.distance_travelled >= vehicle.route.map_exit);
+                let Some(next) = next else {
+                    position += 1;
+                    continue;
+                };
+
+                let mut vehicle = junction.vehicles.remove(position);
+                record_exit(
+                    &mut vehicle,
+                    &mut junction.statistics,
+                    &mut junction.completed_vehicles,
+                    simulation,
+                );
+
+                // Stay in the lane and take one of the movements it allows
+                let turns: Vec<Turn> = geometry.lanes.lanes()[vehicle.slot].turns().collect();
+                let turn = turns[simulation.rng.gen_range(0..turns.len())];
+                let lane = Lane::for_turn(vehicle.lane.to.opposite(), turn);
+                let distance = vehicle.distance_travelled - vehicle.route.map_exit;
+                let end_of_step = simulation.time() + simulation.dt;
+                let handed_off = vehicle.hand_off(lane, &geometry, distance, end_of_step);
+                simulation.emit(SimEvent::HandedOff {
+                    id: handed_off.id,
+                    lane,
+                    column: next % self.grid.columns,
+                    row: next / self.grid.columns,
+                });
+                arrivals.push((next, handed_off));
+            }
+        }
+        for (next, vehicle) in arrivals {
+            self.junctions[next].vehicles.push(vehicle);
+        }
+    }
+
+    /**
+     * Looks for collisions and close calls among all the vehicles of the
+     * network, in network coordinates, with the simulation's safety monitor.
+     * A collision is booked by the intersection it happened on and a close
+     * call by that of its later-spawned vehicle, as the breakdowns count it.
+     */
+    fn check_safety(&mut self, simulation: &mut Simulation) {
+        let geometry = simulation.geometry;
+        let end_of_step = simulation.time() + simulation.dt;
+
+        let placed = self.placed_vehicles(&geometry);
+        for (a, b) in Collision::overlapping_pairs(&placed) {
+            let mut record = CollisionRecord::new(end_of_step, &placed[a], &placed[b]);
+            let index = self.junction_at(record.position, &geometry);
+            let (left, top) = self.junctions[index].origin(&geometry);
+            record.position = (record.position.0 - left, record.position.1 - top);
+            record_collision(record, &mut self.junctions[index].statistics, simulation);
+        }
+
+        let mut vehicles = Vec::with_capacity(placed.len());
+        let mut origins = Vec::with_capacity(placed.len());
+        let mut owners = Vec::with_capacity(placed.len());
+        for (index, junction) in self.junctions.iter().enumerate() {
+            let origin = junction.origin(&geometry);
+            for vehicle in &junction.vehicles {
+                vehicles.push(vehicle.clone());
+                origins.push(origin);
+                owners.push(index);
+            }
+        }
+        for conflict in simulation
+            .safety
+            .observe_placed(&vehicles, &origins, end_of_step)
+        {
+            for junction in &mut self.junctions {
+                for vehicle in junction
+                    .vehicles
+                    .iter_mut()
+                    .filter(|v| conflict.vehicles.contains(&v.id))
+                {
+                    vehicle.had_close_call = true;
+                }
+            }
+            let later = vehicles
+                .iter()
+                .position(|v| v.id == conflict.vehicles[1])
+                .map(|position| owners[position])
+                .expect("close calls are between vehicles on the network");
+            record_close_call(conflict, &mut self.junctions[later].statistics, simulation);
+        }
+    }
+
+    /// Index of the intersection whose map holds the point `(x, y)` in network coordinates.
+    pub fn junction_at(&self, (x, y): (f32, f32), geometry: &IntersectionGeometry) -> usize {
+        let cell = |position: f32, count: usize| {
+            ((position / geometry.size).max(0.0) as usize).min(count - 1)
+        };
+        cell(y, self.grid.rows) * self.grid.columns + cell(x, self.grid.columns)
+    }
+
+    /// Copies of every vehicle on the network, positioned in network coordinates.
+    pub fn placed_vehicles(&self, geometry: &IntersectionGeometry) -> Vec<Vehicle> {
+        let mut placed = Vec::with_capacity(self.vehicle_count());
+        for junction in &self.junctions {
+            let (left, top) = junction.origin(geometry);
+            placed.extend(junction.vehicles.iter().map(|vehicle| Vehicle {
+                x: vehicle.x + left,
+                y: vehicle.y + top,
+                ..vehicle.clone()
+            }));
+        }
+        placed
+    }
+
+    /// The live figures for the whole network at simulation time `now`, for the overlay.
+    pub fn live(&self, now: f32) -> LiveStats {
+        let mut queues = SIDES.map(|side| (side, 0));
+        let mut close_calls = 0;
+        for junction in &self.junctions {
+            let live = junction.statistics.live(&junction.vehicles, now);
+            for ((_, total), (_, queue)) in queues.iter_mut().zip(live.queues) {
+                *total += queue;
+            }
+            close_calls += live.close_calls;
+        }
+        LiveStats {
+            on_screen: self.vehicle_count(),
+            completed: self.vehicles_left(),
+            last_minute: self
+                .exit_times
+                .iter()
+                .rev()
+                .take_while(|&&time| time > now - THROUGHPUT_WINDOW)
+                .count(),
+            queues,
+            close_calls,
+        }
+    }
+
+    /// One line per intersection, then the totals for the network.
+    pub fn summary_lines(&self) -> Vec<String> {
+        let mut lines: Vec<String> = self
+            .junctions
+            .iter()
+            .map(|junction| {
+                let statistics = &junction.statistics;
+                format!(
+                    "Intersection ({}, {}): {} passed, mean delay {:.2} s, {} close calls, {} collisions",
+                    junction.column,
+                    junction.row,
+                    statistics.max_vehicles_passed,
+                    statistics.mean_delay,
+                    statistics.close_calls,
+                    statistics.collisions
+                )
+            })
+            .collect();
+        let total = |count: fn(&Statistics) -> usize| {
+            self.junctions
+                .iter()
+                .map(|junction| count(&junction.statistics))
+                .sum::<usize>()
+        };
+        lines.push(format!(
+            "Vehicles that left the network: {}",
+            self.vehicles_left()
+        ));
+        lines.push(format!("Vehicles on the network: {}", self.vehicle_count()));
+        lines.push(format!("Close Calls: {}", total(|s| s.close_calls)));
+        lines.push(format!("Collisions: {}", total(|s| s.collisions)));
+        lines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sim::{Event, Level};
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    /// A vehicle driving west to east across intersection `index`, `distance` short of the map edge.
+    fn place(network: &mut RoadNetwork, simulation: &mut Simulation, index: usize, distance: f32) {
+        let lane = Lane::set(Direction::West, Direction::East);
+        let id = simulation.next_vehicle_id;
+        simulation.next_vehicle_id += 1;
+        let mut vehicle = Vehicle::new(id, lane, 1, &simulation.geometry, 0.0, &mut simulation.rng);
+        vehicle.travel(vehicle.route.map_exit - distance);
+        network.junctions[index].vehicles.push(vehicle);
+    }
+
+    #[test]
+    fn grids_are_written_columns_by_rows() {
+        let grid = Grid::parse("3x2").unwrap();
+        assert_eq!((grid.columns, grid.rows, grid.count()), (3, 2, 6));
+        assert_eq!(grid.to_string(), "3x2");
+        assert_eq!(
+            Grid::parse("3").unwrap_err(),
+            "expected COLUMNSxROWS, got 3"
+        );
+        for text in ["0x2", "9x1", "ax2", "2x-1"] {
+            assert!(
+                Grid::parse(text)
+                    .unwrap_err()
+                    .contains("is not a number of intersections from 1 to 8"),
+                "{}",
+                text
+            );
+        }
+    }
+
+    #[test]
+    fn neighbours_stop_at_the_edge_of_the_grid() {
+        let grid = Grid::parse("3x2").unwrap();
+        assert_eq!(grid.neighbour(0, Direction::North), None);
+        assert_eq!(grid.neighbour(0, Direction::West), None);
+        assert_eq!(grid.neighbour(0, Direction::East), Some(1));
+        assert_eq!(grid.neighbour(0, Direction::South), Some(3));
+        assert_eq!(grid.neighbour(4, Direction::North), Some(1));
+        assert_eq!(grid.neighbour(4, Direction::West), Some(3));
+        assert_eq!(grid.neighbour(4, Direction::East), Some(5));
+        assert_eq!(grid.neighbour(4, Direction::South), None);
+    }
+
+    #[test]
+    fn vehicles_only_enter_on_the_edge_of_the_grid() {
+        let simulation = Simulation::with_seed(0);
+        let network = RoadNetwork::new(Grid::parse("3x2").unwrap(), &simulation).unwrap();
+        let entries = network.entries();
+        assert_eq!(entries.len(), 10);
+        assert!(!entries.contains(&(1, Direction::South)));
+        assert!(entries.contains(&(1, Direction::North)));
+        assert!(entries.contains(&(4, Direction::South)));
+        assert_eq!(
+            network.size(&simulation.geometry),
+            (
+                3.0 * simulation.geometry.size,
+                2.0 * simulation.geometry.size
+            )
+        );
+    }
+
+    #[test]
+    fn a_vehicle_reaching_a_shared_edge_moves_on_in_the_same_lane_slot() {
+        let mut simulation = Simulation::with_seed(0);
+        let handed_off = Rc::new(RefCell::new(Vec::new()));
+        let sink = Rc::clone(&handed_off);
+        simulation
+            .events
+            .subscribe(Level::Debug, move |event: &Event| {
+                if let SimEvent::HandedOff { .. } = event.kind {
+                    sink.borrow_mut().push(event.kind.clone());
+                }
+            });
+        let mut network = RoadNetwork::new(Grid::parse("2x1").unwrap(), &simulation).unwrap();
+        place(&mut network, &mut simulation, 0, 0.1);
+
+        network.step(&mut Spawner::new(), &mut simulation);
+
+        assert!(network.junctions[0].vehicles.is_empty());
+        let journeys = &network.junctions[0].statistics.journeys;
+        assert_eq!(journeys.len(), 1);
+        assert_eq!(journeys[0].exit_time, simulation.time());
+        assert_eq!(network.vehicles_left(), 0);
+        let vehicle = &network.junctions[1].vehicles[0];
+        assert_eq!(vehicle.spawn_time, simulation.time());
+        assert_eq!(vehicle.lane.from, Direction::West);
+        assert_eq!(vehicle.slot, 1);
+        assert!(vehicle.distance_travelled < 5.0);
+        assert_eq!(vehicle.box_enter_time, None);
+        let handed_off = handed_off.borrow();
+        assert_eq!(handed_off.len(), 1);
+        assert!(matches!(
+            handed_off[0],
+            SimEvent::HandedOff {
+                column: 1,
+                row: 0,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn a_queue_backs_up_across_a_shared_edge() {
+        let mut simulation = Simulation::with_seed(0);
+        let mut network = RoadNetwork::new(Grid::parse("2x1").unwrap(), &simulation).unwrap();
+        place(&mut network, &mut simulation, 0, 20.0);
+        let lane = Lane::set(Direction::West, Direction::East);
+        let ahead = Vehicle::new(9, lane, 1, &simulation.geometry, 0.0, &mut simulation.rng);
+        network.junctions[1].vehicles.push(ahead);
+        let lane = Lane::set(Direction::West, Direction::North);
+        let other_slot = Vehicle::new(10, lane, 2, &simulation.geometry, 0.0, &mut simulation.rng);
+        network.junctions[1].vehicles.push(other_slot);
+
+        network.follow_across_edges();
+
+        let follower = &network.junctions[0].vehicles[0];
+        assert_eq!(follower.leader_beyond_map, Some(follower.route.map_exit));
+        assert_eq!(network.junctions[1].vehicles[0].leader_beyond_map, None);
+    }
+
+    #[test]
+    fn collisions_across_a_shared_edge_are_booked_where_they_happen() {
+        let mut simulation = Simulation::with_seed(0);
+        let mut network = RoadNetwork::new(Grid::parse("2x1").unwrap(), &simulation).unwrap();
+        // The front of the first 20 px short of the edge, the second just over it
+        place(&mut network, &mut simulation, 0, 20.0);
+        let lane = Lane::set(Direction::West, Direction::East);
+        let ahead = Vehicle::new(9, lane, 1, &simulation.geometry, 0.0, &mut simulation.rng);
+        network.junctions[1].vehicles.push(ahead);
+
+        network.step(&mut Spawner::new(), &mut simulation);
+
+        let statistics = &network.junctions[0].statistics;
+        assert_eq!(statistics.collisions, 1);
+        assert_eq!(network.junctions[1].statistics.collisions, 0);
+        let record = &statistics.collision_records[0];
+        assert_eq!(record.vehicles, [0, 9]);
+        let size = simulation.geometry.size;
+        assert!(record.position.0 > size - VEHICLE_SIZE && record.position.0 < size);
+    }
+
+    #[test]
+    fn points_are_looked_up_in_the_intersection_holding_them() {
+        let simulation = Simulation::with_seed(0);
+        let network = RoadNetwork::new(Grid::parse("3x2").unwrap(), &simulation).unwrap();
+        let geometry = simulation.geometry;
+        let size = geometry.size;
+        assert_eq!(network.junction_at((10.0, 10.0), &geometry), 0);
+        assert_eq!(
+            network.junction_at((size + 10.0, size + 10.0), &geometry),
+            4
+        );
+        // Points off the network belong to the nearest intersection
+        assert_eq!(network.junction_at((-5.0, 3.0 * size), &geometry), 3);
+        assert_eq!(network.junction_at((4.0 * size, -5.0), &geometry), 2);
+    }
+
+    #[test]
+    fn vehicles_leave_the_network_at_its_edge() {
+        let mut simulation = Simulation::with_seed(0);
+        let mut network = RoadNetwork::new(Grid::parse("1x1").unwrap(), &simulation).unwrap();
+        place(&mut network, &mut simulation, 0, 0.0);
+        let mut requests = Spawner::new();
+        while network.vehicle_count() > 0 {
+            network.step(&mut requests, &mut simulation);
+        }
+        assert_eq!(network.vehicles_left(), 1);
+        assert_eq!(network.live(simulation.time()).last_minute, 1);
+        let lines = network.summary_lines();
+        assert_eq!(lines[1], "Vehicles that left the network: 1");
+        assert_eq!(lines[2], "Vehicles on the network: 0");
+    }
+}
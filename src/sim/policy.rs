@@ -0,0 +1,276 @@
+use crate::sim::types::Admission;
+use crate::sim::{
+    Collision, Lane, ReservationManager, RouteConflicts, Signal, SignalTimings, TrafficLight,
+    Vehicle,
+};
+
+/// What a policy gets to look at when deciding: every vehicle, which routes meet and the clock.
+pub struct World<'a> {
+    pub vehicles: &'a [Vehicle],
+    pub routes: &'a RouteConflicts,
+    /// Simulation time (s) at the start of the tick.
+    pub now: f32,
+    pub dt: f32,
+}
+
+/**
+ * Decides which vehicles may enter the intersection box.
+ *
+ * `traffic_manager` calls `begin_tick` once per tick, then `admit` for each
+ * vehicle right before it moves. The answer is stored on the vehicle and
+ * read by `Vehicle::update` when the vehicle reaches its stop line.
+ */
+pub trait IntersectionPolicy {
+    /// Name used to select the policy on the command line.
+    fn name(&self) -> &'static str;
+
+    fn begin_tick(&mut self, _world: &World) {}
+
+    fn admit(&mut self, vehicle: &Vehicle, world: &World) -> Admission;
+
+    /// Signal shown to `lane`, for policies that have signal heads to draw.
+    fn signal_for(&self, _lane: &Lane) -> Option<Signal> {
+        None
+    }
+}
+
+/// Names accepted by `policy_from_name`, for usage messages.
+pub const POLICY_NAMES: [&str; 5] = [
+    "occupancy",
+    "single-occupancy",
+    "reservation",
+    "fixed-time",
+    "actuated",
+];
+
+pub fn policy_from_name(
+    name: &str,
+    signals: &SignalTimings,
+) -> Result<Box<dyn IntersectionPolicy>, String> {
+    match name {
+        "occupancy" => Ok(Box::new(ConflictOccupancy)),
+        "single-occupancy" => Ok(Box::new(SingleOccupancy)),
+        "reservation" => Ok(Box::new(ReservationManager::new())),
+        "fixed-time" => Ok(Box::new(TrafficLight::fixed_time(signals)?)),
+        "actuated" => Ok(Box::new(TrafficLight::actuated(signals)?)),
+        _ => Err(format!(
+            "unknown intersection policy: {} (expected one of {})",
+            name,
+            POLICY_NAMES.join(", ")
+        )),
+    }
+}
+
+/// Straight and left-turning vehicles wait while a conflicting movement uses the box.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ConflictOccupancy;
+
+impl IntersectionPolicy for ConflictOccupancy {
+    fn name(&self) -> &'static str {
+        "occupancy"
+    }
+
+    fn admit(&mut self, vehicle: &Vehicle, world: &World) -> Admission {
+        if Collision::should_wait_for_intersection(vehicle, world.vehicles, world.routes) {
+            Admission::Stop
+        } else {
+            Admission::Go
+        }
+    }
+}
+
+/// The original rule: straight and left-turning vehicles wait while any vehicle
+/// is entering or inside the box; right turns only for vehicles whose route meets theirs.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SingleOccupancy;
+
+impl IntersectionPolicy for SingleOccupancy {
+    fn name(&self) -> &'static str {
+        "single-occupancy"
+    }
+
+    fn admit(&mut self, vehicle: &Vehicle, world: &World) -> Admission {
+        if vehicle.lane.is_right_turn() {
+            return if Collision::is_right_turn_blocked(vehicle, world.vehicles, world.routes) {
+                Admission::Stop
+            } else {
+                Admission::Go
+            };
+        }
+
+        let occupied = world.vehicles.iter().any(|v| {
+            v.id != vehicle.id
+                && (Collision::is_vehicle_in_intersection(v)
+                    || Collision::is_vehicle_entering_intersection(v))
+        });
+
+        if occupied {
+            Admission::Stop
+        } else {
+            Admission::Go
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sim::types::{Direction, VehicleState};
+    use crate::sim::{IntersectionGeometry, LaneLayout, Turn};
+    use rand::{SeedableRng, rngs::StdRng};
+
+    /// A vehicle on `lane`, `distance` short of its stop line (past it when negative).
+    fn vehicle(id: usize, lane: Lane, distance: f32) -> Vehicle {
+        vehicle_in(&IntersectionGeometry::STANDARD, id, lane, distance)
+    }
+
+    /// As `vehicle`, in the first inbound lane `lane` may use under `geometry`.
+    fn vehicle_in(
+        geometry: &IntersectionGeometry,
+        id: usize,
+        lane: Lane,
+        distance: f32,
+    ) -> Vehicle {
+        let slot = geometry.lane_slots(&lane)[0];
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut vehicle = Vehicle::new(id, lane, slot, geometry, 0.0, &mut rng);
+        vehicle.travel(vehicle.route.stop_line - distance);
+        vehicle
+    }
+
+    /// What `policy` tells `vehicle`, with the routes of the vehicle's geometry.
+    fn admit(
+        policy: &mut dyn IntersectionPolicy,
+        vehicle: &Vehicle,
+        vehicles: &[Vehicle],
+    ) -> Admission {
+        let routes = RouteConflicts::new(&vehicle.geometry);
+        let world = World {
+            vehicles,
+            routes: &routes,
+            now: 0.0,
+            dt: 1.0 / 60.0,
+        };
+        policy.begin_tick(&world);
+        policy.admit(vehicle, &world)
+    }
+
+    #[test]
+    fn every_listed_name_builds_its_policy() {
+        for name in POLICY_NAMES {
+            assert_eq!(
+                policy_from_name(name, &SignalTimings::default())
+                    .unwrap()
+                    .name(),
+                name
+            );
+        }
+        let error = policy_from_name("roundabout", &SignalTimings::default())
+            .err()
+            .unwrap();
+        assert!(error.contains("roundabout"));
+        assert!(error.contains(&POLICY_NAMES.join(", ")));
+    }
+
+    #[test]
+    fn occupancy_policies_differ_on_compatible_movements_in_the_box() {
+        use Direction::*;
+
+        let mut waiting = vehicle(1, Lane::set(North, South), 0.0);
+        waiting.state = VehicleState::Waiting;
+        let opposing = vehicle(2, Lane::set(South, North), -135.0);
+        let vehicles = [waiting.clone(), opposing];
+
+        assert_eq!(
+            admit(&mut ConflictOccupancy, &waiting, &vehicles),
+            Admission::Go
+        );
+        assert_eq!(
+            admit(&mut SingleOccupancy, &waiting, &vehicles),
+            Admission::Stop
+        );
+
+        let right = vehicle(3, Lane::set(North, West), 0.0);
+        assert_eq!(
+            admit(&mut SingleOccupancy, &right, &vehicles),
+            Admission::Go
+        );
+    }
+
+    #[test]
+    fn right_turns_wait_only_for_routes_that_meet_theirs() {
+        let south_right = Lane::for_turn(Direction::South, Turn::Right);
+        let east_right = Lane::for_turn(Direction::East, Turn::Right);
+        let single_lane = IntersectionGeometry {
+            lanes: LaneLayout::parse("rsl").unwrap(),
+            ..IntersectionGeometry::STANDARD
+        };
+
+        for (geometry, expected) in [
+            (IntersectionGeometry::STANDARD, Admission::Go),
+            (single_lane, Admission::Stop),
+        ] {
+            let turning = vehicle_in(&geometry, 1, south_right, 0.0);
+            let in_box = vehicle_in(&geometry, 2, east_right, -20.0);
+            let vehicles = [turning.clone(), in_box];
+            for policy in [
+                &mut ConflictOccupancy as &mut dyn IntersectionPolicy,
+                &mut SingleOccupancy,
+            ] {
+                assert_eq!(
+                    admit(policy, &turning, &vehicles),
+                    expected,
+                    "{} with lanes {}",
+                    policy.name(),
+                    geometry.lanes
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn shared_lanes_wait_for_every_movement_they_allow_to_cross() {
+        use Direction::*;
+
+        // From a shared straight and left lane a straight vehicle still waits
+        // for the crossing traffic
+        let shared = IntersectionGeometry {
+            lanes: LaneLayout::parse("r,sl").unwrap(),
+            ..IntersectionGeometry::STANDARD
+        };
+        let mut waiting = vehicle_in(&shared, 1, Lane::set(North, South), 0.0);
+        waiting.state = VehicleState::Waiting;
+        waiting.stop_line_time = Some(0.0);
+        let crossing = vehicle_in(&shared, 2, Lane::set(East, West), -40.0);
+        let vehicles = [waiting.clone(), crossing];
+        assert_eq!(
+            admit(&mut ConflictOccupancy, &waiting, &vehicles),
+            Admission::Stop
+        );
+        assert_eq!(
+            admit(
+                &mut ConflictOccupancy,
+                &waiting,
+                std::slice::from_ref(&waiting)
+            ),
+            Admission::Go
+        );
+    }
+
+    #[test]
+    fn only_signal_policies_show_signals() {
+        let lane = Lane::ALL[0];
+        for name in POLICY_NAMES {
+            let shows = policy_from_name(name, &SignalTimings::default())
+                .unwrap()
+                .signal_for(&lane)
+                .is_some();
+            assert_eq!(
+                shows,
+                name == "fixed-time" || name == "actuated",
+                "{}",
+                name
+            );
+        }
+    }
+}
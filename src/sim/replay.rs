@@ -0,0 +1,199 @@
+use crate::sim::types::Direction;
+use crate::sim::{LaneLayout, Scenario, SignalTimings, Simulation, Spawner};
+use serde::{Deserialize, Serialize};
+
+/// An input that changes what the simulation does, as raised by the keyboard.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum InputAction {
+    /// Spawn a vehicle on the given approach (the arrow keys).
+    Spawn { from: Direction },
+    /// Switch the random spawning mode on or off (`R`).
+    ToggleRandom,
+}
+
+impl InputAction {
+    pub fn apply(&self, spawner: &mut Spawner) {
+        match self {
+            InputAction::Spawn { from } => match from {
+                Direction::North => spawner.spawn_north = true,
+                Direction::South => spawner.spawn_south = true,
+                Direction::East => spawner.spawn_east = true,
+                Direction::West => spawner.spawn_west = true,
+            },
+            InputAction::ToggleRandom => spawner.spawn_random = !spawner.spawn_random,
+        }
+    }
+}
+
+/// An input together with the tick whose step first sees it.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct RecordedAction {
+    pub tick: u64,
+    #[serde(flatten)]
+    pub action: InputAction,
+}
+
+/**
+ * Everything needed to play a session again: the seed, policy, signal
+ * timings and lane layout the simulation started with, the scenario it ran,
+ * and every input by tick.
+ *
+ * Physics only depends on the tick count, so pausing and time scaling are
+ * not part of the recording.
+ */
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Recording {
+    pub seed: u64,
+    pub policy: String,
+    pub signals: SignalTimings,
+    pub lanes: LaneLayout,
+    #[serde(default)]
+    pub scenario: Option<Scenario>,
+    /// Number of ticks the session ran for.
+    pub ticks: u64,
+    pub actions: Vec<RecordedAction>,
+}
+
+impl Recording {
+    pub fn new(simulation: &Simulation, scenario: Option<Scenario>) -> Self {
+        Recording {
+            seed: simulation.seed,
+            policy: simulation.policy.name().to_string(),
+            signals: simulation.signals,
+            lanes: simulation.geometry.lanes,
+            scenario,
+            ticks: simulation.tick,
+            actions: Vec::new(),
+        }
+    }
+
+    pub fn record(&mut self, tick: u64, action: InputAction) {
+        self.actions.push(RecordedAction { tick, action });
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("cannot encode replay: {}", e))?;
+        std::fs::write(path, json).map_err(|e| format!("cannot write replay {}: {}", path, e))
+    }
+
+    pub fn load(path: &str) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("cannot read replay {}: {}", path, e))?;
+        let recording: Recording =
+            serde_json::from_str(&text).map_err(|e| format!("invalid replay {}: {}", path, e))?;
+        if let Some(scenario) = &recording.scenario {
+            scenario.validate()?;
+        }
+        Ok(recording)
+    }
+}
+
+/// Feeds a recording's inputs back into the spawner at their original ticks.
+#[derive(Clone, Debug)]
+pub struct Replay {
+    pub recording: Recording,
+    /// Index of the next action to play.
+    next: usize,
+}
+
+impl Replay {
+    pub fn new(mut recording: Recording) -> Self {
+        recording.actions.sort_by_key(|recorded| recorded.tick);
+        Replay { recording, next: 0 }
+    }
+
+    /// Applies the inputs recorded for `tick`; call right before that tick's step.
+    pub fn apply_due(&mut self, tick: u64, spawner: &mut Spawner) {
+        while let Some(recorded) = self.recording.actions.get(self.next)
+            && recorded.tick <= tick
+        {
+            recorded.action.apply(spawner);
+            self.next += 1;
+        }
+    }
+
+    /// Whether the recorded session had ended by `tick`.
+    pub fn is_finished(&self, tick: u64) -> bool {
+        tick >= self.recording.ticks
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sim::run_replay;
+
+    fn recording() -> Recording {
+        let mut simulation = Simulation::with_seed(5);
+        simulation.signals.yellow = Some(4.0);
+        let mut recording = Recording::new(&simulation, None);
+        recording.ticks = 1800;
+        recording.record(
+            240,
+            InputAction::Spawn {
+                from: Direction::East,
+            },
+        );
+        recording.record(0, InputAction::ToggleRandom);
+        recording.record(600, InputAction::ToggleRandom);
+        recording
+    }
+
+    #[test]
+    fn inputs_are_applied_at_their_recorded_ticks() {
+        let mut replay = Replay::new(recording());
+        let mut spawner = Spawner::new();
+
+        replay.apply_due(0, &mut spawner);
+        assert!(spawner.spawn_random);
+        replay.apply_due(239, &mut spawner);
+        assert!(!spawner.spawn_east);
+        replay.apply_due(240, &mut spawner);
+        assert!(spawner.spawn_east);
+        replay.apply_due(1000, &mut spawner);
+        assert!(!spawner.spawn_random);
+
+        assert!(!replay.is_finished(1799));
+        assert!(replay.is_finished(1800));
+    }
+
+    #[test]
+    fn recordings_survive_a_round_trip_through_json() {
+        let recording = recording();
+        let json = serde_json::to_string(&recording).unwrap();
+        assert!(
+            json.contains(r#""action":"spawn","from":"east""#),
+            "{}",
+            json
+        );
+        let read: Recording = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            (read.seed, read.policy.as_str(), read.ticks),
+            (5, "occupancy", 1800)
+        );
+        assert_eq!(read.signals, recording.signals);
+        assert_eq!(read.lanes, recording.lanes);
+
+        // The layout is part of every recording
+        let mut value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        value.as_object_mut().unwrap().remove("lanes");
+        let error = serde_json::from_value::<Recording>(value).unwrap_err();
+        assert!(error.to_string().contains("lanes"), "{}", error);
+        assert_eq!(read.actions, recording.actions);
+    }
+
+    #[test]
+    fn a_replay_plays_the_same_session_every_time() {
+        let play = || {
+            let recording = recording();
+            let mut simulation = Simulation::with_seed(recording.seed);
+            let statistics = run_replay(&mut simulation, Replay::new(recording), None, None);
+            (simulation.tick, statistics.summary_lines())
+        };
+        let (ticks, summary) = play();
+        assert_eq!(ticks, 1800);
+        assert_eq!(play(), (ticks, summary));
+    }
+}
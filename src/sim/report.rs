@@ -0,0 +1,303 @@
+use crate::sim::{
+    Grid, LaneLayout, RoadNetwork, SafetyThresholds, Simulation, Statistics, TICKS_PER_SECOND,
+};
+use serde::Serialize;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::Path;
+
+/// What a set of statistics came from.
+#[derive(Clone, Debug, Serialize)]
+pub struct RunMetadata {
+    pub seed: u64,
+    pub policy: String,
+    /// Scenario file or name, `None` for keyboard or random traffic.
+    pub scenario: Option<String>,
+    /// Inbound lanes of every approach.
+    pub lanes: LaneLayout,
+    pub ticks: u64,
+    /// Simulated seconds.
+    pub duration: f64,
+    /// Close call thresholds the run was measured with.
+    pub safety: SafetyThresholds,
+}
+
+impl RunMetadata {
+    pub fn new(simulation: &Simulation, scenario: Option<String>) -> Self {
+        RunMetadata {
+            seed: simulation.seed,
+            policy: simulation.policy.name().to_string(),
+            scenario,
+            lanes: simulation.geometry.lanes,
+            ticks: simulation.tick,
+            // From the tick count, so whole seconds stay exact
+            duration: simulation.tick as f64 / TICKS_PER_SECOND as f64,
+            safety: simulation.safety.thresholds,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct Report<'a> {
+    metadata: &'a RunMetadata,
+    statistics: &'a Statistics,
+}
+
+/**
+ * Writes the statistics of a run to `path`.
+ *
+ * A `.csv` file gets one row per run, appended after a header written when
+ * the file is new, so many runs can share a file. Any other extension gets
+ * a pretty-printed JSON document, replacing the file.
+ *
+ * # Returns
+ *
+ * A Result with an error string if the file could not be written.
+ */
+pub fn export_statistics(
+    path: &str,
+    metadata: &RunMetadata,
+    statistics: &Statistics,
+) -> Result<(), String> {
+    let is_csv = Path::new(path)
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("csv"));
+    let result = if is_csv {
+        append_csv(path, metadata, statistics)
+    } else {
+        let report = Report {
+            metadata,
+            statistics,
+        };
+        serde_json::to_string_pretty(&report)
+            .map_err(std::io::Error::other)
+            .and_then(|json| std::fs::write(path, json + "\n"))
+    };
+    result.map_err(|e| format!("cannot write statistics {}: {}", path, e))
+}
+
+#[derive(Serialize)]
+struct IntersectionReport<'a> {
+    column: usize,
+    row: usize,
+    statistics: &'a Statistics,
+}
+
+#[derive(Serialize)]
+struct NetworkReport<'a> {
+    metadata: &'a RunMetadata,
+    grid: Grid,
+    vehicles_left: usize,
+    intersections: Vec<IntersectionReport<'a>>,
+}
+
+/**
+ * Writes the statistics of a road network run to `path` as a pretty-printed
+ * JSON document: the run metadata, then the statistics of each intersection.
+ *
+ * # Returns
+ *
+ * A Result with an error string if the file could not be written; a `.csv`
+ * path is refused, one row per run cannot hold several intersections.
+ */
+pub fn export_network_statistics(
+    path: &str,
+    metadata: &RunMetadata,
+    network: &RoadNetwork,
+) -> Result<(), String> {
+    if Path::new(path)
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("csv"))
+    {
+        return Err(format!(
+            "cannot write statistics {}: a road network is saved as JSON",
+            path
+        ));
+    }
+    let report = NetworkReport {
+        metadata,
+        grid: network.grid,
+        vehicles_left: network.vehicles_left(),
+        intersections: network
+            .junctions
+            .iter()
+            .map(|junction| IntersectionReport {
+                column: junction.column,
+                row: junction.row,
+                statistics: &junction.statistics,
+            })
+            .collect(),
+    };
+    serde_json::to_string_pretty(&report)
+        .map_err(std::io::Error::other)
+        .and_then(|json| std::fs::write(path, json + "\n"))
+        .map_err(|e| format!("cannot write statistics {}: {}", path, e))
+}
+
+fn append_csv(path: &str, metadata: &RunMetadata, statistics: &Statistics) -> std::io::Result<()> {
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    if file.metadata()?.len() == 0 {
+        writeln!(
+            file,
+            "seed,policy,scenario,lanes,ticks,duration,vehicles_passed,max_velocity,min_velocity,\
+             max_crossing_time,min_crossing_time,close_calls,collisions,mean_box_time,mean_delay,\
+             crossing_time_mean,crossing_time_p50,crossing_time_p90,crossing_time_p99,\
+             crossing_time_std,speed_mean,speed_p50,speed_p90,speed_p99,speed_std,\
+             waiting_time_mean,waiting_time_p50,waiting_time_p90,waiting_time_p99,\
+             waiting_time_std"
+        )?;
+    }
+    write!(
+        file,
+        "{},{},{},{},{},{},{},{},{},{},{},{},{},{},{}",
+        metadata.seed,
+        csv_field(&metadata.policy),
+        csv_field(metadata.scenario.as_deref().unwrap_or("")),
+        csv_field(&metadata.lanes.to_string()),
+        metadata.ticks,
+        metadata.duration,
+        statistics.max_vehicles_passed,
+        statistics.max_velocity,
+        statistics.min_velocity,
+        statistics.max_crossing_time,
+        statistics.min_crossing_time,
+        statistics.close_calls,
+        statistics.collisions,
+        statistics.mean_box_time,
+        statistics.mean_delay,
+    )?;
+    for distribution in [
+        statistics.crossing_time,
+        statistics.speed,
+        statistics.waiting_time,
+    ] {
+        write!(
+            file,
+            ",{},{},{},{},{}",
+            distribution.mean,
+            distribution.p50,
+            distribution.p90,
+            distribution.p99,
+            distribution.std_dev,
+        )?;
+    }
+    writeln!(file)
+}
+
+/// Quotes a CSV value when it contains a separator, quote or line break.
+pub fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sim::Distribution;
+
+    fn metadata(scenario: Option<&str>) -> RunMetadata {
+        let mut simulation = Simulation::with_seed(9);
+        simulation.tick = 90;
+        RunMetadata::new(&simulation, scenario.map(str::to_string))
+    }
+
+    fn statistics() -> Statistics {
+        Statistics {
+            max_vehicles_passed: 4,
+            max_velocity: 72.0,
+            min_velocity: 0.0,
+            max_crossing_time: 6.5,
+            min_crossing_time: 3.25,
+            close_calls: 1,
+            collisions: 2,
+            crossing_time: Distribution::from_values(&[2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0]),
+            ..Statistics::new()
+        }
+    }
+
+    /// A fresh file name in the temporary directory, removed if left over.
+    fn temp_path(name: &str) -> String {
+        let path = std::env::temp_dir().join(format!("smart-road-{}-{}", std::process::id(), name));
+        let _ = std::fs::remove_file(&path);
+        path.to_str().unwrap().to_string()
+    }
+
+    #[test]
+    fn metadata_counts_the_duration_in_ticks() {
+        let metadata = metadata(None);
+        assert_eq!(metadata.seed, 9);
+        assert_eq!(metadata.ticks, 90);
+        assert_eq!(metadata.duration, 1.5);
+    }
+
+    #[test]
+    fn json_reports_hold_the_metadata_and_the_statistics() {
+        let path = temp_path("report.json");
+        export_statistics(&path, &metadata(Some("rush hour")), &statistics()).unwrap();
+        let text = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let report: serde_json::Value = serde_json::from_str(&text).unwrap();
+        assert_eq!(report["metadata"]["seed"], 9);
+        assert_eq!(report["metadata"]["scenario"], "rush hour");
+        assert_eq!(report["metadata"]["ticks"], 90);
+        assert_eq!(report["statistics"]["max_vehicles_passed"], 4);
+        assert_eq!(report["statistics"]["close_calls"], 1);
+    }
+
+    #[test]
+    fn csv_reports_append_one_row_per_run_under_a_single_header() {
+        let path = temp_path("report.csv");
+        export_statistics(&path, &metadata(None), &statistics()).unwrap();
+        export_statistics(&path, &metadata(Some("a, \"b\"")), &statistics()).unwrap();
+        let text = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("seed,policy,scenario,lanes,ticks,duration,"));
+        let policy = Simulation::with_seed(0).policy.name().to_string();
+        assert_eq!(
+            lines[1],
+            format!(
+                "9,{},,\"r,s,l\",90,1.5,4,72,0,6.5,3.25,1,2,0,0,5,4,9,9,2,0,0,0,0,0,0,0,0,0,0",
+                policy
+            )
+        );
+        // The quoted lane layout holds two of the row's commas
+        assert_eq!(lines[0].split(',').count() + 2, lines[1].split(',').count());
+        assert!(lines[2].starts_with(&format!("9,{},\"a, \"\"b\"\"\",\"r,s,l\",90,", policy)));
+    }
+
+    #[test]
+    fn csv_fields_are_quoted_only_when_needed() {
+        assert_eq!(csv_field("plain"), "plain");
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+    }
+
+    #[test]
+    fn network_reports_list_every_intersection_and_refuse_csv() {
+        let simulation = Simulation::with_seed(9);
+        let network = RoadNetwork::new(Grid::parse("2x1").unwrap(), &simulation).unwrap();
+        let path = temp_path("network.json");
+        export_network_statistics(&path, &metadata(None), &network).unwrap();
+        let text = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let report: serde_json::Value = serde_json::from_str(&text).unwrap();
+        assert_eq!(report["grid"]["columns"], 2);
+        assert_eq!(report["vehicles_left"], 0);
+        assert_eq!(report["intersections"][1]["column"], 1);
+        assert_eq!(report["intersections"][1]["row"], 0);
+
+        let path = temp_path("network.csv");
+        let error = export_network_statistics(&path, &metadata(None), &network).unwrap_err();
+        assert!(
+            error.ends_with("a road network is saved as JSON"),
+            "{}",
+            error
+        );
+        assert!(!Path::new(&path).exists());
+    }
+}
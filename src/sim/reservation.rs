@@ -0,0 +1,414 @@
+use crate::sim::types::{Admission, Direction, VehicleState};
+use crate::sim::{CRUISE_SPEED, IntersectionPolicy, VEHICLE_SIZE, Vehicle, World};
+use std::collections::HashMap;
+
+/// Length of one reservation time slot in seconds.
+const SLOT_LENGTH: f32 = 0.1;
+/// Extra slots held before and after each predicted occupancy to absorb speed error.
+const SLOT_BUFFER: i64 = 1;
+
+/// Vehicles start requesting a reservation this close to the stop line (px).
+pub const REQUEST_DISTANCE: f32 = 150.0;
+/// Minimum time between two requests from the same vehicle after a rejection.
+const RETRY_INTERVAL: f32 = 0.25;
+/// Latest arrival the manager will offer, relative to the earliest possible one.
+const MAX_DELAY: f32 = 4.0;
+/// Minimum gap between two arrivals from the same lane (safe distance plus a car length).
+const LANE_HEADWAY: f32 = 0.8;
+
+/// Answer to a reservation request.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum ReservationResponse {
+    Accept { arrival_time: f32 },
+    Reject,
+}
+
+/**
+ * Autonomous intersection manager.
+ *
+ * The box is covered by a grid of tiles, one lane width square, and time
+ * is cut into slots. An
+ * approaching vehicle asks for a trajectory through the box; the manager
+ * replays the vehicle's route at cruising speed, and if every (slot, tile)
+ * pair it would cover is free for some arrival time, books them and answers
+ * with that arrival time. The vehicle then paces itself to reach the stop
+ * line at exactly that time.
+ */
+#[derive(Debug, Clone, Default)]
+pub struct ReservationManager {
+    /// Booked (slot, tile) pairs and the vehicle holding each one.
+    tiles: HashMap<(i64, usize), usize>,
+    /// Latest arrival time granted to each inbound lane, by approach and slot.
+    lane_last_arrival: HashMap<(Direction, usize), f32>,
+    /// Time of the last rejected request per vehicle id.
+    last_request: HashMap<usize, f32>,
+    pub accepted: usize,
+    pub rejected: usize,
+    /// Reservations given up because the vehicle missed its arrival time.
+    pub cancelled: usize,
+}
+
+impl ReservationManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /**
+     * Tries to book a trajectory through the box for `vehicle`.
+     *
+     * Arrival times are tried from the earliest the vehicle can make at
+     * cruising speed, in slot steps, up to `MAX_DELAY` later.
+     */
+    pub fn request(&mut self, vehicle: &Vehicle, now: f32, dt: f32) -> ReservationResponse {
+        let path = Self::predict_path(vehicle, dt);
+
+        let mut earliest = now + vehicle.distance_to_stop_line().max(0.0) / CRUISE_SPEED;
+        let inbound_lane = (vehicle.lane.from, vehicle.slot);
+        if let Some(&last) = self.lane_last_arrival.get(&inbound_lane) {
+            earliest = earliest.max(last + LANE_HEADWAY);
+        }
+
+        let mut arrival_time = earliest;
+        while arrival_time <= earliest + MAX_DELAY {
+            if self.is_free(&path, arrival_time, vehicle.id) {
+                self.book(&path, arrival_time, vehicle.id);
+                self.lane_last_arrival.insert(inbound_lane, arrival_time);
+                self.accepted += 1;
+                return ReservationResponse::Accept { arrival_time };
+            }
+            arrival_time += SLOT_LENGTH;
+        }
+
+        self.rejected += 1;
+        ReservationResponse::Reject
+    }
+
+    fn is_free(&self, path: &[(f32, Vec<usize>)], arrival_time: f32, id: usize) -> bool {
+        path.iter().all(|(offset, tiles)| {
+            let slot = slot_at(arrival_time + offset);
+            (slot - SLOT_BUFFER..=slot + SLOT_BUFFER).all(|s| {
+                tiles.iter().all(|&tile| {
+                    self.tiles
+                        .get(&(s, tile))
+                        .is_none_or(|&holder| holder == id)
+                })
+            })
+        })
+    }
+
+    /// Releases every tile booked for vehicle `id`.
+    fn cancel(&mut self, id: usize) {
+        self.tiles.retain(|_, holder| *holder != id);
+        self.cancelled += 1;
+    }
+
+    fn book(&mut self, path: &[(f32, Vec<usize>)], arrival_time: f32, id: usize) {
+        for (offset, tiles) in path {
+            let slot = slot_at(arrival_time + offset);
+            for s in slot - SLOT_BUFFER..=slot + SLOT_BUFFER {
+                for &tile in tiles {
+                    self.tiles.insert((s, tile), id);
+                }
+            }
+        }
+    }
+
+    /**
+     * Replays the vehicle's route from the stop line at cruising speed.
+     *
+     * # Returns
+     *
+     * For every timestep while the footprint overlaps the box, the time since
+     * reaching the stop line and the tiles covered.
+     */
+    fn predict_path(vehicle: &Vehicle, dt: f32) -> Vec<(f32, Vec<usize>)> {
+        let mut ghost = vehicle.clone();
+        ghost.travel(ghost.distance_to_stop_line());
+        ghost.state = VehicleState::Approaching;
+        ghost.speed = CRUISE_SPEED;
+
+        let mut path = Vec::new();
+        let mut offset = 0.0;
+        // A crossing never takes more than a few seconds; the cap guards against loops
+        for _ in 0..1000 {
+            let tiles = footprint_tiles(&ghost);
+            if tiles.is_empty() && ghost.state == VehicleState::Exiting {
+                break;
+            }
+            if !tiles.is_empty() {
+                path.push((offset, tiles));
+            }
+            ghost.advance(dt);
+            offset += dt;
+        }
+        path
+    }
+
+    /// A vehicle only requests once no unreserved vehicle of its lane is closer to the stop line.
+    fn is_first_in_lane(vehicle: &Vehicle, vehicles: &[Vehicle]) -> bool {
+        let distance = vehicle.distance_to_stop_line();
+        !vehicles.iter().any(|other| {
+            other.id != vehicle.id
+                && other.shares_lane_with(vehicle)
+                && matches!(
+                    other.state,
+                    VehicleState::Approaching | VehicleState::Waiting
+                )
+                && !matches!(other.admission, Admission::Reserved { .. })
+                && other.distance_to_stop_line() > -10.0
+                && other.distance_to_stop_line() < distance
+        })
+    }
+}
+
+impl IntersectionPolicy for ReservationManager {
+    fn name(&self) -> &'static str {
+        "reservation"
+    }
+
+    /// Drops bookings for slots that are already in the past, and the
+    /// retry times of vehicles that have left.
+    fn begin_tick(&mut self, world: &World) {
+        let oldest = slot_at(world.now) - SLOT_BUFFER - 1;
+        self.tiles.retain(|&(slot, _), _| slot >= oldest);
+        self.last_request
+            .retain(|id, _| world.vehicles.iter().any(|vehicle| vehicle.id == *id));
+    }
+
+    /**
+     * Decides whether `vehicle` may enter, sending a request on its behalf
+     * when it is the first unreserved vehicle of its lane within
+     * `REQUEST_DISTANCE` of the stop line. A vehicle still short of the stop
+     * line more than a slot after its arrival time would run into tiles
+     * booked for others, so its reservation is cancelled and it asks again.
+     */
+    fn admit(&mut self, vehicle: &Vehicle, world: &World) -> Admission {
+        let now = world.now;
+        if let Admission::Reserved { arrival_time } = vehicle.admission {
+            if now <= arrival_time + SLOT_LENGTH || vehicle.distance_to_stop_line() <= 0.0 {
+                return vehicle.admission;
+            }
+            self.cancel(vehicle.id);
+        }
+
+        let distance = vehicle.distance_to_stop_line();
+        let before_box = matches!(
+            vehicle.state,
+            VehicleState::Approaching | VehicleState::Waiting
+        ) && distance > -10.0;
+        if !before_box
+            || distance > REQUEST_DISTANCE
+            || !Self::is_first_in_lane(vehicle, world.vehicles)
+        {
+            return Admission::Stop;
+        }
+
+        if let Some(&last) = self.last_request.get(&vehicle.id)
+            && now - last < RETRY_INTERVAL
+        {
+            return Admission::Stop;
+        }
+
+        match self.request(vehicle, now, world.dt) {
+            ReservationResponse::Accept { arrival_time } => {
+                self.last_request.remove(&vehicle.id);
+                Admission::Reserved { arrival_time }
+            }
+            ReservationResponse::Reject => {
+                self.last_request.insert(vehicle.id, now);
+                Admission::Stop
+            }
+        }
+    }
+}
+
+fn slot_at(time: f32) -> i64 {
+    (time / SLOT_LENGTH).floor() as i64
+}
+
+/// Indices of the box tiles overlapped by the bounds of a vehicle's footprint, turned with its heading.
+fn footprint_tiles(vehicle: &Vehicle) -> Vec<usize> {
+    let geometry = &vehicle.geometry;
+    let box_size = geometry.box_max() - geometry.box_min();
+    let grid_size = 2 * geometry.lanes_per_approach();
+    let tile_size = geometry.lane_width;
+    let (sin, cos) = vehicle.heading.sin_cos();
+    let reach = VEHICLE_SIZE / 2.0 * (sin.abs() + cos.abs());
+    let (cx, cy) = vehicle.centre();
+    let span = |centre: f32| -> Option<(usize, usize)> {
+        let low = centre - reach - geometry.box_min();
+        let high = centre + reach - geometry.box_min();
+        if high <= 0.0 || low >= box_size {
+            return None;
+        }
+        let first = (low.max(0.0) / tile_size) as usize;
+        let last = ((high.min(box_size) - 0.001) / tile_size) as usize;
+        Some((first, last.min(grid_size - 1)))
+    };
+
+    let (Some((col_start, col_end)), Some((row_start, row_end))) = (span(cx), span(cy)) else {
+        return Vec::new();
+    };
+
+    let mut tiles = Vec::new();
+    for row in row_start..=row_end {
+        for col in col_start..=col_end {
+            tiles.push(row * grid_size + col);
+        }
+    }
+    tiles
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sim::types::Direction;
+    use crate::sim::{IntersectionGeometry, Lane, RouteConflicts};
+    use rand::{SeedableRng, rngs::StdRng};
+    use std::sync::LazyLock;
+
+    const DT: f32 = 1.0 / 60.0;
+
+    static ROUTES: LazyLock<RouteConflicts> =
+        LazyLock::new(|| RouteConflicts::new(&IntersectionGeometry::STANDARD));
+
+    /// A vehicle making the movement `from` -> `to`, `distance` short of its stop line.
+    fn vehicle(id: usize, from: Direction, to: Direction, distance: f32) -> Vehicle {
+        let lane = Lane::set(from, to);
+        let geometry = IntersectionGeometry::STANDARD;
+        let slot = geometry.lane_slots(&lane)[0];
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut vehicle = Vehicle::new(id, lane, slot, &geometry, 0.0, &mut rng);
+        vehicle.travel(vehicle.route.stop_line - distance);
+        vehicle
+    }
+
+    fn world(vehicles: &[Vehicle]) -> World<'_> {
+        World {
+            vehicles,
+            routes: &ROUTES,
+            now: 0.0,
+            dt: DT,
+        }
+    }
+
+    fn arrival(response: ReservationResponse) -> f32 {
+        match response {
+            ReservationResponse::Accept { arrival_time } => arrival_time,
+            ReservationResponse::Reject => panic!("request rejected"),
+        }
+    }
+
+    #[test]
+    fn a_crossing_request_waits_for_the_tiles_booked_before_it() {
+        use Direction::*;
+
+        // Coming from the north a vehicle crosses the lane from the east soon
+        // after its stop line, while one from the east reaches that crossing
+        // near the far side: from level starts they pass it at different times
+        let mut manager = ReservationManager::new();
+        let first = arrival(manager.request(&vehicle(1, East, West, 50.0), 0.0, DT));
+        assert!((first - 50.0 / CRUISE_SPEED).abs() < 1e-4);
+        assert_eq!(
+            arrival(manager.request(&vehicle(2, North, South, 50.0), 0.0, DT)),
+            first
+        );
+
+        // With the vehicle from the east well ahead they would meet there
+        let mut manager = ReservationManager::new();
+        manager.request(&vehicle(1, East, West, 10.0), 0.0, DT);
+        let earliest = 150.0 / CRUISE_SPEED;
+        let second = arrival(manager.request(&vehicle(2, North, South, 150.0), 0.0, DT));
+        assert!(second > earliest + SLOT_LENGTH, "{} {}", earliest, second);
+        assert_eq!(manager.accepted, 2);
+    }
+
+    #[test]
+    fn opposing_straight_movements_share_the_box() {
+        use Direction::*;
+
+        let mut manager = ReservationManager::new();
+        let first = arrival(manager.request(&vehicle(1, North, South, 50.0), 0.0, DT));
+        let second = arrival(manager.request(&vehicle(2, South, North, 50.0), 0.0, DT));
+        assert_eq!(second, first);
+    }
+
+    #[test]
+    fn vehicles_of_one_lane_arrive_a_headway_apart() {
+        use Direction::*;
+
+        let mut manager = ReservationManager::new();
+        let first = arrival(manager.request(&vehicle(1, West, East, 20.0), 0.0, DT));
+        let second = arrival(manager.request(&vehicle(2, West, East, 50.0), 0.0, DT));
+        assert!(second >= first + LANE_HEADWAY);
+    }
+
+    #[test]
+    fn only_the_first_vehicle_of_a_lane_near_the_stop_line_requests() {
+        use Direction::*;
+
+        let mut manager = ReservationManager::new();
+        let far = vehicle(1, West, East, REQUEST_DISTANCE + 10.0);
+        assert_eq!(
+            manager.admit(&far, &world(std::slice::from_ref(&far))),
+            Admission::Stop
+        );
+
+        let lead = vehicle(2, West, East, 40.0);
+        let follower = vehicle(3, West, East, 80.0);
+        let vehicles = [lead.clone(), follower.clone()];
+        assert_eq!(manager.admit(&follower, &world(&vehicles)), Admission::Stop);
+        assert!(matches!(
+            manager.admit(&lead, &world(&vehicles)),
+            Admission::Reserved { .. }
+        ));
+        assert_eq!(manager.accepted, 1);
+    }
+
+    #[test]
+    fn a_reservation_missed_by_more_than_a_slot_is_cancelled() {
+        use Direction::*;
+
+        let mut manager = ReservationManager::new();
+        let mut late = vehicle(1, West, East, 30.0);
+        let arrival_time = arrival(manager.request(&late, 0.0, DT));
+        late.admission = Admission::Reserved { arrival_time };
+        let mut world = world(std::slice::from_ref(&late));
+
+        world.now = arrival_time + SLOT_LENGTH;
+        assert_eq!(manager.admit(&late, &world), late.admission);
+        assert_eq!(manager.cancelled, 0);
+
+        world.now = arrival_time + 2.0 * SLOT_LENGTH;
+        // It asks again from where it is, with its old tiles released
+        let booked = manager.tiles.len();
+        let Admission::Reserved {
+            arrival_time: retried,
+        } = manager.admit(&late, &world)
+        else {
+            panic!("no new reservation");
+        };
+        assert!(retried > world.now);
+        assert_eq!(manager.cancelled, 1);
+        assert_eq!(manager.accepted, 2);
+        assert_eq!(manager.tiles.len(), booked);
+
+        // Once over the stop line it is committed, however late
+        let mut entering = vehicle(2, North, South, -5.0);
+        entering.admission = Admission::Reserved { arrival_time };
+        assert_eq!(manager.admit(&entering, &world), entering.admission);
+        assert_eq!(manager.cancelled, 1);
+    }
+
+    #[test]
+    fn retry_times_are_forgotten_once_a_vehicle_has_left() {
+        use Direction::*;
+
+        let mut manager = ReservationManager::new();
+        let waiting = vehicle(1, West, East, 30.0);
+        manager.last_request.insert(1, 0.0);
+        manager.last_request.insert(2, 0.0);
+        manager.begin_tick(&world(std::slice::from_ref(&waiting)));
+        assert_eq!(manager.last_request.keys().collect::<Vec<_>>(), [&1]);
+    }
+}
@@ -0,0 +1,446 @@
+use crate::sim::types::{Direction, VehicleState};
+use crate::sim::{Collision, Lane, VEHICLE_SIZE, Vehicle};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+
+/// Vehicles are treated as circles of half the sprite size (px).
+const VEHICLE_RADIUS: f32 = VEHICLE_SIZE / 2.0;
+
+/// Interval between predicted positions for TTC (s).
+const PREDICTION_STEP: f32 = 0.1;
+
+/// Side of a post-encroachment grid cell (px); well above the distance a vehicle covers in one tick.
+const CELL_SIZE: f32 = 10.0;
+
+/// Below these values a pair of vehicles counts as a close call.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize)]
+pub struct SafetyThresholds {
+    /// Time-to-collision (s).
+    pub ttc: f32,
+    /// Post-encroachment time (s).
+    pub pet: f32,
+}
+
+impl Default for SafetyThresholds {
+    fn default() -> Self {
+        SafetyThresholds { ttc: 1.5, pet: 1.0 }
+    }
+}
+
+/// The surrogate safety measure that flagged a conflict.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SafetyMeasure {
+    /// Time until two vehicles would touch if both kept their speed along their routes.
+    Ttc,
+    /// Time between one vehicle leaving a spot in the box and another reaching it.
+    Pet,
+}
+
+/// A close call between two vehicles, counted once per pair.
+#[derive(Clone, Debug, Serialize)]
+pub struct Conflict {
+    /// Simulation time (s).
+    pub time: f32,
+    /// The two vehicles, smaller id first.
+    pub vehicles: [usize; 2],
+    pub lanes: [Lane; 2],
+    pub measure: SafetyMeasure,
+    /// The measured TTC or PET (s).
+    pub value: f32,
+}
+
+#[derive(Copy, Clone)]
+struct CellVisit {
+    id: usize,
+    from: Direction,
+    /// Last time the vehicle's centre was in the cell.
+    time: f32,
+}
+
+/**
+ * Watches every pair of vehicles for close calls by time-to-collision and
+ * post-encroachment time, reporting each pair the first time it crosses a
+ * threshold.
+ */
+pub struct SafetyMonitor {
+    pub thresholds: SafetyThresholds,
+    /// Pairs already reported, while both vehicles are still on the map.
+    reported: HashSet<(usize, usize)>,
+    /// Last visit to each grid cell the centre of a vehicle in the box passed over,
+    /// kept while the vehicle is on the map and the visit recent enough to count.
+    cells: HashMap<(i32, i32), CellVisit>,
+}
+
+impl Default for SafetyMonitor {
+    fn default() -> Self {
+        Self::new(SafetyThresholds::default())
+    }
+}
+
+impl SafetyMonitor {
+    pub fn new(thresholds: SafetyThresholds) -> Self {
+        SafetyMonitor {
+            thresholds,
+            reported: HashSet::new(),
+            cells: HashMap::new(),
+        }
+    }
+
+    /**
+     * Checks `vehicles`, positioned as of simulation time `now`, for new conflicts.
+     *
+     * TTC is measured for pairs with at least one vehicle in the box that are
+     * closing in on each other, following each vehicle's route. PET is
+     * measured where vehicles from different approaches pass over the same
+     * spot in the box. Vehicles one behind the other in the same inbound
+     * lane, or in the same outbound lane once both joined it, are kept apart
+     * by car following and do not count for TTC. Pairs and visits of
+     * vehicles that have left are forgotten.
+     *
+     * # Returns
+     *
+     * The conflicts of pairs not reported before.
+     */
+    pub fn observe(&mut self, vehicles: &[Vehicle], now: f32) -> Vec<Conflict> {
+        self.observe_placed(vehicles, &vec![(0.0, 0.0); vehicles.len()], now)
+    }
+
+    /**
+     * Like `observe`, for the vehicles of several intersections at once, each
+     * in the coordinates of its own map, whose top-left corner is at the
+     * matching entry of `origins` in the shared frame the pairs are measured in.
+     * Lanes are only shared within an intersection.
+     */
+    pub fn observe_placed(
+        &mut self,
+        vehicles: &[Vehicle],
+        origins: &[(f32, f32)],
+        now: f32,
+    ) -> Vec<Conflict> {
+        let mut conflicts = Vec::new();
+
+        let present: HashSet<usize> = vehicles.iter().map(|v| v.id).collect();
+        self.reported
+            .retain(|(a, b)| present.contains(a) && present.contains(b));
+        let pet = self.thresholds.pet;
+        self.cells
+            .retain(|_, visit| present.contains(&visit.id) && now - visit.time < pet);
+
+        let paths: Vec<Vec<(f32, f32)>> = vehicles
+            .iter()
+            .zip(origins)
+            .map(|(v, &origin)| {
+                predict(v, self.thresholds.ttc)
+                    .into_iter()
+                    .map(|point| shift(point, origin))
+                    .collect()
+            })
+            .collect();
+        // How far each path reaches from where it starts, to pass over pairs that are far apart
+        let spans: Vec<f32> = paths
+            .iter()
+            .map(|path| path.windows(2).map(|step| distance(step[0], step[1])).sum())
+            .collect();
+        for (i, a) in vehicles.iter().enumerate() {
+            for (j, b) in vehicles.iter().enumerate().skip(i + 1) {
+                let same_map = origins[i] == origins[j];
+                if same_map && (a.shares_lane_with(b) || a.shares_outbound_lane_with(b))
+                    || !Collision::is_vehicle_in_intersection(a)
+                        && !Collision::is_vehicle_in_intersection(b)
+                    || distance(paths[i][0], paths[j][0])
+                        > spans[i] + spans[j] + 2.0 * VEHICLE_RADIUS
+                {
+                    continue;
+                }
+                if let Some(ttc) = time_to_collision(&paths[i], &paths[j])
+                    && ttc < self.thresholds.ttc
+                {
+                    self.report(a, b, SafetyMeasure::Ttc, ttc, now, &mut conflicts);
+                }
+            }
+        }
+
+        for (vehicle, &origin) in vehicles
+            .iter()
+            .zip(origins)
+            .filter(|(v, _)| Collision::is_vehicle_in_intersection(v))
+        {
+            let cell = cell_of(shift(vehicle.centre(), origin));
+            if let Some(&visit) = self.cells.get(&cell)
+                && visit.id != vehicle.id
+                && visit.from != vehicle.lane.from
+            {
+                let pet = now - visit.time;
+                if pet < self.thresholds.pet
+                    && let Some(other) = vehicles.iter().find(|v| v.id == visit.id)
+                {
+                    self.report(other, vehicle, SafetyMeasure::Pet, pet, now, &mut conflicts);
+                }
+            }
+            self.cells.insert(
+                cell,
+                CellVisit {
+                    id: vehicle.id,
+                    from: vehicle.lane.from,
+                    time: now,
+                },
+            );
+        }
+
+        conflicts
+    }
+
+    fn report(
+        &mut self,
+        a: &Vehicle,
+        b: &Vehicle,
+        measure: SafetyMeasure,
+        value: f32,
+        now: f32,
+        conflicts: &mut Vec<Conflict>,
+    ) {
+        let (first, second) = if a.id < b.id { (a, b) } else { (b, a) };
+        if self.reported.insert((first.id, second.id)) {
+            conflicts.push(Conflict {
+                time: now,
+                vehicles: [first.id, second.id],
+                lanes: [first.lane, second.lane],
+                measure,
+                value,
+            });
+        }
+    }
+}
+
+/**
+ * Centre positions of `vehicle` every `PREDICTION_STEP` seconds from now until
+ * `horizon`, driving its route, turns included, at its current speed. A
+ * waiting vehicle stays where it is.
+ */
+fn predict(vehicle: &Vehicle, horizon: f32) -> Vec<(f32, f32)> {
+    let steps = (horizon / PREDICTION_STEP).ceil() as usize;
+    let speed = if vehicle.state == VehicleState::Waiting {
+        0.0
+    } else {
+        vehicle.speed
+    };
+    (0..=steps)
+        .map(|step| {
+            let ahead = speed * step as f32 * PREDICTION_STEP;
+            vehicle.route.pose_at(vehicle.distance_travelled + ahead).0
+        })
+        .collect()
+}
+
+/**
+ * Seconds until two predicted paths come within touching distance, `None` if
+ * they do not or the vehicles are not closing in on each other over the first
+ * prediction step, such as a pair that is standing still.
+ */
+fn time_to_collision(a: &[(f32, f32)], b: &[(f32, f32)]) -> Option<f32> {
+    let reach = 2.0 * VEHICLE_RADIUS;
+    let gaps: Vec<f32> = a
+        .iter()
+        .zip(b)
+        .map(|((ax, ay), (bx, by))| (ax - bx).powi(2) + (ay - by).powi(2))
+        .collect();
+    if gaps.len() < 2 || gaps[1] >= gaps[0] {
+        return None;
+    }
+    gaps.iter()
+        .position(|gap| *gap < reach * reach)
+        .map(|step| step as f32 * PREDICTION_STEP)
+}
+
+fn distance(a: (f32, f32), b: (f32, f32)) -> f32 {
+    ((a.0 - b.0).powi(2) + (a.1 - b.1).powi(2)).sqrt()
+}
+
+fn shift((x, y): (f32, f32), (left, top): (f32, f32)) -> (f32, f32) {
+    (x + left, y + top)
+}
+
+fn cell_of((x, y): (f32, f32)) -> (i32, i32) {
+    (
+        (x / CELL_SIZE).floor() as i32,
+        (y / CELL_SIZE).floor() as i32,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sim::{IntersectionGeometry, LaneLayout};
+    use rand::{SeedableRng, rngs::StdRng};
+
+    /// A vehicle at 60 px/s, `distance` short of its stop line (past it when negative).
+    fn vehicle(id: usize, from: Direction, to: Direction, distance: f32) -> Vehicle {
+        let lane = Lane::set(from, to);
+        let geometry = IntersectionGeometry::STANDARD;
+        let slot = geometry.lane_slots(&lane)[0];
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut vehicle = Vehicle::new(id, lane, slot, &geometry, 0.0, &mut rng);
+        vehicle.travel(vehicle.route.stop_line - distance);
+        vehicle.speed = 60.0;
+        vehicle
+    }
+
+    #[test]
+    fn crossing_paths_in_the_box_are_a_close_call_by_ttc() {
+        // Both centres reach (347.5, 452.5) in about a second
+        let south = vehicle(0, Direction::North, Direction::South, -115.0);
+        let east = vehicle(1, Direction::West, Direction::East, -7.5);
+        let mut monitor = SafetyMonitor::default();
+
+        let conflicts = monitor.observe(&[south.clone(), east.clone()], 2.0);
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].vehicles, [0, 1]);
+        assert_eq!(conflicts[0].measure, SafetyMeasure::Ttc);
+        assert!(conflicts[0].value < 1.0);
+        assert_eq!(conflicts[0].time, 2.0);
+
+        // A pair is only reported once
+        assert!(monitor.observe(&[south, east], 2.1).is_empty());
+    }
+
+    #[test]
+    fn pairs_are_measured_where_their_maps_are_placed() {
+        let south = vehicle(0, Direction::North, Direction::South, -115.0);
+        let east = vehicle(1, Direction::West, Direction::East, -7.5);
+        let size = IntersectionGeometry::STANDARD.size;
+
+        let mut monitor = SafetyMonitor::default();
+        let apart = [(0.0, 0.0), (size, 0.0)];
+        assert!(
+            monitor
+                .observe_placed(&[south.clone(), east.clone()], &apart, 2.0)
+                .is_empty()
+        );
+        let together = [(size, 0.0), (size, 0.0)];
+        assert_eq!(
+            monitor.observe_placed(&[south, east], &together, 2.0).len(),
+            1
+        );
+    }
+
+    #[test]
+    fn followers_and_pairs_outside_the_box_do_not_count() {
+        let leader = vehicle(0, Direction::North, Direction::South, -135.0);
+        let follower = vehicle(1, Direction::North, Direction::South, -115.0);
+        assert!(
+            SafetyMonitor::default()
+                .observe(&[leader, follower], 0.0)
+                .is_empty()
+        );
+
+        let south = vehicle(0, Direction::North, Direction::South, 165.0);
+        let west = vehicle(1, Direction::North, Direction::West, 165.0);
+        assert!(
+            SafetyMonitor::default()
+                .observe(&[south, west], 0.0)
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn followers_in_a_shared_outbound_lane_do_not_count() {
+        // The right turn from the west and the straight from the north leave by the same lane
+        let geometry = IntersectionGeometry {
+            lanes: LaneLayout::parse("rs,s,l").unwrap(),
+            ..IntersectionGeometry::STANDARD
+        };
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut follower = Vehicle::new(
+            0,
+            Lane::set(Direction::North, Direction::South),
+            0,
+            &geometry,
+            0.0,
+            &mut rng,
+        );
+        follower.speed = 60.0;
+        let mut leader = Vehicle::new(
+            1,
+            Lane::set(Direction::West, Direction::South),
+            0,
+            &geometry,
+            0.0,
+            &mut rng,
+        );
+        // Just out of the box, with the follower still in it close behind
+        leader.travel(leader.route.joins_exit() + 5.0);
+        leader.speed = 0.0;
+        let left = leader.route.map_exit - leader.distance_travelled;
+        follower.travel(follower.route.map_exit - left - VEHICLE_SIZE - 5.0);
+
+        assert!(Collision::is_vehicle_in_intersection(&follower));
+        assert!(follower.shares_outbound_lane_with(&leader));
+        let ttc = time_to_collision(&predict(&follower, 1.5), &predict(&leader, 1.5));
+        assert!(ttc.is_some());
+        assert!(
+            SafetyMonitor::default()
+                .observe(&[follower, leader], 0.0)
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn pairs_that_are_not_closing_in_do_not_count() {
+        let standing = [(0.0, 0.0); 3];
+        let beside = [(20.0, 0.0); 3];
+        assert_eq!(time_to_collision(&standing, &beside), None);
+        let closing = [(20.0, 0.0), (10.0, 0.0), (0.0, 0.0)];
+        assert_eq!(time_to_collision(&standing, &closing), Some(0.0));
+        let parting = [(20.0, 0.0), (30.0, 0.0), (40.0, 0.0)];
+        assert_eq!(time_to_collision(&standing, &parting), None);
+
+        // Stopped side by side where their paths cross
+        let mut south = vehicle(0, Direction::North, Direction::South, -175.0);
+        let mut east = vehicle(1, Direction::West, Direction::East, -67.5);
+        south.speed = 0.0;
+        east.speed = 0.0;
+        assert!(
+            SafetyMonitor::new(SafetyThresholds { ttc: 1.5, pet: 0.1 })
+                .observe(&[south, east], 0.0)
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn passing_a_spot_just_after_another_approach_is_a_close_call_by_pet() {
+        let east = vehicle(0, Direction::West, Direction::East, -77.5);
+        let east_later = vehicle(0, Direction::West, Direction::East, -167.5);
+        let mut south = vehicle(1, Direction::North, Direction::South, -182.5);
+        // Stopped, so TTC leaves the pair alone
+        south.speed = 0.0;
+        south.state = VehicleState::Waiting;
+
+        let thresholds = SafetyThresholds { ttc: 0.1, pet: 1.0 };
+        let mut monitor = SafetyMonitor::new(thresholds);
+        assert!(monitor.observe(std::slice::from_ref(&east), 0.0).is_empty());
+        let conflicts = monitor.observe(&[east_later.clone(), south.clone()], 0.5);
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].measure, SafetyMeasure::Pet);
+        assert_eq!(conflicts[0].value, 0.5);
+
+        let mut monitor = SafetyMonitor::new(thresholds);
+        monitor.observe(&[east], 0.0);
+        assert!(monitor.observe(&[east_later, south], 1.5).is_empty());
+    }
+
+    #[test]
+    fn vehicles_that_left_are_forgotten() {
+        let south = vehicle(0, Direction::North, Direction::South, -115.0);
+        let east = vehicle(1, Direction::West, Direction::East, -7.5);
+        let mut monitor = SafetyMonitor::default();
+        assert_eq!(monitor.observe(&[south.clone(), east], 2.0).len(), 1);
+        assert!(!monitor.cells.is_empty());
+
+        monitor.observe(std::slice::from_ref(&south), 2.1);
+        assert!(monitor.reported.is_empty());
+        assert!(monitor.cells.values().all(|visit| visit.id == 0));
+        monitor.observe(&[], 2.2);
+        assert!(monitor.cells.is_empty());
+    }
+}
@@ -0,0 +1,374 @@
+use crate::sim::types::{Direction, VehicleColor};
+use crate::sim::{Lane, LaneLayout, SignalTimings};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/**
+ * A reproducible traffic pattern: vehicles spawned at fixed times plus
+ * random (Poisson) arrival streams per approach.
+ *
+ * Loaded from TOML, or JSON when the file name ends in `.json`:
+ *
+ * ```toml
+ * name = "morning rush"
+ * lanes = ["rs", "s", "l", "l"]   # optional, one entry per inbound lane
+ *
+ * [signals]        # optional, for the traffic light policies
+ * green = 8.0      # through green; all of yellow, all_red, left_green and
+ * max_green = 30.0 # max_green can be set the same way
+ *
+ * [[spawns]]
+ * time = 1.5
+ * from = "south"
+ * to = "west"
+ * color = "pink"   # optional, random otherwise
+ * speed = 60.0     # optional initial speed in px/s
+ *
+ * [[arrivals]]
+ * from = "north"
+ * per_minute = 20.0
+ * start = 10.0     # optional, defaults to 0
+ * end = 70.0       # optional, open-ended otherwise
+ * ```
+ */
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Scenario {
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Inbound lanes of every approach, the standard `r,s,l` when absent.
+    #[serde(default)]
+    pub lanes: Option<LaneLayout>,
+    /// Signal durations, the plan's defaults for anything not set.
+    #[serde(default)]
+    pub signals: SignalTimings,
+    #[serde(default)]
+    pub spawns: Vec<TimedSpawn>,
+    #[serde(default)]
+    pub arrivals: Vec<ArrivalRate>,
+}
+
+/// A single vehicle entering at a given simulation time (s).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TimedSpawn {
+    pub time: f32,
+    pub from: Direction,
+    pub to: Direction,
+    #[serde(default)]
+    pub color: Option<VehicleColor>,
+    #[serde(default)]
+    pub speed: Option<f32>,
+}
+
+/// Poisson arrivals on one approach between `start` and `end` (s).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ArrivalRate {
+    pub from: Direction,
+    /// Destination of every arrival, a random one like the arrow keys when absent.
+    #[serde(default)]
+    pub to: Option<Direction>,
+    /// Mean number of vehicles per simulated minute.
+    pub per_minute: f32,
+    #[serde(default)]
+    pub start: f32,
+    #[serde(default)]
+    pub end: Option<f32>,
+}
+
+impl Scenario {
+    /**
+     * Reads and validates a scenario file.
+     *
+     * # Arguments
+     *
+     * * `path` - A `.json` file, or a TOML file for any other extension.
+     *
+     * # Returns
+     *
+     * A Result containing the scenario or an error string.
+     */
+    pub fn load(path: &str) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("cannot read scenario {}: {}", path, e))?;
+
+        let is_json = Path::new(path)
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("json"));
+        let scenario: Scenario = if is_json {
+            serde_json::from_str(&text).map_err(|e| format!("invalid scenario {}: {}", path, e))?
+        } else {
+            toml::from_str(&text).map_err(|e| format!("invalid scenario {}: {}", path, e))?
+        };
+
+        scenario.validate()?;
+        Ok(scenario)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        self.signals.validate()?;
+
+        for spawn in &self.spawns {
+            if spawn.from == spawn.to {
+                return Err(format!(
+                    "spawn at {}s turns back to {:?}",
+                    spawn.time, spawn.from
+                ));
+            }
+            if !spawn.time.is_finite() || spawn.time < 0.0 {
+                return Err(format!("spawn time must not be negative: {}", spawn.time));
+            }
+            if spawn
+                .speed
+                .is_some_and(|speed| !speed.is_finite() || speed <= 0.0)
+            {
+                return Err(format!("spawn at {}s needs a positive speed", spawn.time));
+            }
+        }
+
+        for arrival in &self.arrivals {
+            if arrival.to == Some(arrival.from) {
+                return Err(format!("arrivals from {:?} turn back", arrival.from));
+            }
+            if !arrival.per_minute.is_finite() || arrival.per_minute < 0.0 {
+                return Err(format!(
+                    "arrivals from {:?} need a rate of at least 0",
+                    arrival.from
+                ));
+            }
+            if arrival.end.is_some_and(|end| end < arrival.start) {
+                return Err(format!(
+                    "arrivals from {:?} end before they start",
+                    arrival.from
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// A vehicle the scenario wants on the road, waiting for its lane entry to be clear.
+#[derive(Debug, Clone, Copy)]
+pub struct PlannedSpawn {
+    pub lane: Lane,
+    pub color: Option<VehicleColor>,
+    pub speed: Option<f32>,
+    /// Set once the spawn was put off because the lane entry was occupied.
+    pub deferred: bool,
+}
+
+/// Plays a `Scenario` back against the simulation clock.
+#[derive(Debug, Clone)]
+pub struct ScenarioPlayer {
+    pub scenario: Scenario,
+    /// Index of the next timed spawn, `scenario.spawns` being sorted by time.
+    next_spawn: usize,
+    /// Time of the next arrival of each stream, drawn when the stream is first polled.
+    next_arrivals: Vec<Option<f32>>,
+    /// Spawns that are due, oldest first.
+    pub pending: Vec<PlannedSpawn>,
+}
+
+impl ScenarioPlayer {
+    pub fn new(mut scenario: Scenario) -> Self {
+        scenario.spawns.sort_by(|a, b| a.time.total_cmp(&b.time));
+        let next_arrivals = vec![None; scenario.arrivals.len()];
+        ScenarioPlayer {
+            scenario,
+            next_spawn: 0,
+            next_arrivals,
+            pending: Vec::new(),
+        }
+    }
+
+    /**
+     * Queues every spawn due by simulation time `now`.
+     *
+     * Random draws (arrival gaps and destinations) come from `rng`, so the
+     * same seed gives the same arrivals.
+     */
+    pub fn queue_due<R: Rng>(&mut self, now: f32, rng: &mut R) {
+        while let Some(spawn) = self.scenario.spawns.get(self.next_spawn)
+            && spawn.time <= now
+        {
+            self.pending.push(PlannedSpawn {
+                lane: Lane::set(spawn.from, spawn.to),
+                color: spawn.color,
+                speed: spawn.speed,
+                deferred: false,
+            });
+            self.next_spawn += 1;
+        }
+
+        for (arrival, next) in self.scenario.arrivals.iter().zip(&mut self.next_arrivals) {
+            let rate = arrival.per_minute / 60.0;
+            if rate <= 0.0 {
+                continue;
+            }
+
+            let mut time = *next.get_or_insert_with(|| arrival.start + exponential(rate, rng));
+            while time <= now && arrival.end.is_none_or(|end| time <= end) {
+                let to = arrival
+                    .to
+                    .unwrap_or_else(|| random_destination(arrival.from, rng));
+                self.pending.push(PlannedSpawn {
+                    lane: Lane::set(arrival.from, to),
+                    color: None,
+                    speed: None,
+                    deferred: false,
+                });
+                time += exponential(rate, rng);
+            }
+            *next = Some(time);
+        }
+    }
+}
+
+/// Gap until the next event of a Poisson process with `rate` events per second.
+fn exponential<R: Rng>(rate: f32, rng: &mut R) -> f32 {
+    let u: f32 = rng.r#gen();
+    -(1.0 - u).ln() / rate
+}
+
+/// Destination picked by the arrow keys and the random mode: each of the three equally likely.
+pub fn random_destination<R: Rng>(from: Direction, rng: &mut R) -> Direction {
+    match (from, rng.gen_range(0..3)) {
+        (Direction::North | Direction::South, 0) => Direction::East,
+        (Direction::North | Direction::South, 1) => Direction::West,
+        (Direction::East | Direction::West, 0) => Direction::North,
+        (Direction::East | Direction::West, 1) => Direction::South,
+        _ => from.opposite(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{SeedableRng, rngs::StdRng};
+
+    fn parse(text: &str) -> Result<Scenario, String> {
+        let scenario: Scenario = toml::from_str(text).map_err(|e| e.to_string())?;
+        scenario.validate()?;
+        Ok(scenario)
+    }
+
+    #[test]
+    fn rejects_u_turns_negative_times_and_backwards_arrival_windows() {
+        let u_turn = "[[spawns]]\ntime = 1.0\nfrom = \"north\"\nto = \"north\"";
+        assert!(parse(u_turn).unwrap_err().contains("turns back"));
+
+        let negative = "[[spawns]]\ntime = -1.0\nfrom = \"north\"\nto = \"south\"";
+        assert!(
+            parse(negative)
+                .unwrap_err()
+                .contains("must not be negative")
+        );
+
+        let backwards =
+            "[[arrivals]]\nfrom = \"east\"\nper_minute = 10.0\nstart = 20.0\nend = 10.0";
+        assert!(
+            parse(backwards)
+                .unwrap_err()
+                .contains("end before they start")
+        );
+
+        let stopped = "[[spawns]]\ntime = 1.0\nfrom = \"north\"\nto = \"south\"\nspeed = 0.0";
+        assert!(parse(stopped).unwrap_err().contains("positive speed"));
+
+        assert!(
+            parse("[[spawns]]\ntime = 1.0\nfrom = \"north\"\nto = \"south\"\nlane = 2").is_err()
+        );
+    }
+
+    #[test]
+    fn timed_spawns_are_released_in_time_order() {
+        use Direction::*;
+
+        let scenario = parse(
+            "[[spawns]]\ntime = 2.0\nfrom = \"west\"\nto = \"east\"\n\
+             [[spawns]]\ntime = 0.5\nfrom = \"north\"\nto = \"south\"\n\
+             [[spawns]]\ntime = 1.0\nfrom = \"east\"\nto = \"north\"",
+        )
+        .unwrap();
+        let mut player = ScenarioPlayer::new(scenario);
+        let mut rng = StdRng::seed_from_u64(0);
+
+        player.queue_due(0.4, &mut rng);
+        assert!(player.pending.is_empty());
+        player.queue_due(0.7, &mut rng);
+        let lanes = |player: &ScenarioPlayer| -> Vec<Lane> {
+            player.pending.iter().map(|spawn| spawn.lane).collect()
+        };
+        assert_eq!(lanes(&player), [Lane::set(North, South)]);
+        player.queue_due(5.0, &mut rng);
+        assert_eq!(
+            lanes(&player),
+            [
+                Lane::set(North, South),
+                Lane::set(East, North),
+                Lane::set(West, East)
+            ]
+        );
+    }
+
+    #[test]
+    fn a_seed_gives_the_same_poisson_arrivals() {
+        let scenario =
+            parse("[[arrivals]]\nfrom = \"north\"\nper_minute = 30.0\nstart = 5.0\nend = 95.0")
+                .unwrap();
+        let arrivals = |seed| {
+            let mut player = ScenarioPlayer::new(scenario.clone());
+            let mut rng = StdRng::seed_from_u64(seed);
+            let mut arrivals = Vec::new();
+            for second in 0..=120 {
+                player.queue_due(second as f32, &mut rng);
+                for spawn in player.pending.drain(..) {
+                    arrivals.push((second, spawn.lane));
+                }
+            }
+            arrivals
+        };
+
+        let first = arrivals(7);
+        assert_eq!(first, arrivals(7));
+        assert!(first.iter().all(|&(second, lane)| {
+            (5..=95).contains(&second) && lane.from == Direction::North
+        }));
+        // 45 expected over the 90 s window
+        assert!((25..=65).contains(&first.len()), "{}", first.len());
+    }
+
+    #[test]
+    fn shipped_scenario_loads() {
+        let scenario = Scenario::load("scenarios/rush_hour.toml").unwrap();
+        assert_eq!(scenario.name.as_deref(), Some("rush hour"));
+        assert_eq!(scenario.spawns.len(), 3);
+        assert_eq!(scenario.arrivals.len(), 4);
+    }
+
+    #[test]
+    fn reads_and_checks_the_lane_layout() {
+        let scenario = parse("lanes = [\"rs\", \"s\", \"l\", \"l\"]").unwrap();
+        assert_eq!(scenario.lanes.unwrap().to_string(), "rs,s,l,l");
+        assert_eq!(parse("").unwrap().lanes, None);
+        assert!(parse("lanes = [\"l\", \"s\", \"r\"]").is_err());
+    }
+
+    #[test]
+    fn reads_and_checks_the_signal_timings() {
+        let scenario = parse("[signals]\ngreen = 8.0\nall_red = 0.0").unwrap();
+        assert_eq!(scenario.signals.green, Some(8.0));
+        assert_eq!(scenario.signals.all_red, Some(0.0));
+        assert_eq!(scenario.signals.yellow, None);
+        assert_eq!(parse("").unwrap().signals, SignalTimings::default());
+        assert!(
+            parse("[signals]\nyellow = 0.0")
+                .unwrap_err()
+                .contains("yellow")
+        );
+        assert!(parse("[signals]\nred = 2.0").is_err());
+    }
+}
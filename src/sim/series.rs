@@ -0,0 +1,235 @@
+use crate::sim::types::{Direction, VehicleState};
+use crate::sim::{LiveStats, Vehicle};
+use serde::{Serialize, Serializer};
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+/// Samples kept before the oldest are dropped: an hour at one sample per second.
+pub const SERIES_CAPACITY: usize = 3600;
+
+/// The state of the intersection at one simulated second.
+#[derive(Clone, Debug, Serialize)]
+pub struct Sample {
+    /// Simulation time (s).
+    pub time: f32,
+    /// Vehicles that left the screen during the last simulated minute.
+    pub throughput: usize,
+    /// Mean speed of the vehicles on screen, stopped ones counting as zero (px/s).
+    pub mean_speed: f32,
+    /// Vehicles queued before the box, per approach.
+    #[serde(serialize_with = "serialize_queues")]
+    pub queues: [(Direction, usize); 4],
+}
+
+/// Queues as a `{"north": 3, ...}` map rather than a list of pairs.
+fn serialize_queues<S: Serializer>(
+    queues: &[(Direction, usize); 4],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_map(queues.iter().map(|(from, queue)| (from, queue)))
+}
+
+impl Sample {
+    pub fn new(vehicles: &[Vehicle], live: &LiveStats, now: f32) -> Self {
+        let speeds = vehicles.iter().map(|v| {
+            if v.state == VehicleState::Waiting {
+                0.0
+            } else {
+                v.speed
+            }
+        });
+        Sample {
+            time: now,
+            throughput: live.last_minute,
+            mean_speed: speeds.sum::<f32>() / vehicles.len().max(1) as f32,
+            queues: live.queues,
+        }
+    }
+
+    /// Vehicles queued on all approaches.
+    pub fn total_queue(&self) -> usize {
+        self.queues.iter().map(|(_, queue)| queue).sum()
+    }
+}
+
+/// A ring buffer of the last `SERIES_CAPACITY` samples, oldest first.
+#[derive(Clone, Debug, Serialize)]
+#[serde(transparent)]
+pub struct TimeSeries {
+    samples: VecDeque<Sample>,
+}
+
+impl Default for TimeSeries {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TimeSeries {
+    pub fn new() -> Self {
+        TimeSeries {
+            samples: VecDeque::with_capacity(SERIES_CAPACITY),
+        }
+    }
+
+    /// Appends `sample`, dropping the oldest one when the buffer is full.
+    pub fn push(&mut self, sample: Sample) {
+        if self.samples.len() == SERIES_CAPACITY {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(sample);
+    }
+
+    pub fn samples(&self) -> impl ExactSizeIterator<Item = &Sample> {
+        self.samples.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+}
+
+/**
+ * Writes one line per sample to `path`: CSV with a column per approach
+ * queue, or JSON Lines when the file name ends in `.jsonl` or `.json`.
+ *
+ * # Returns
+ *
+ * A Result with an error string if the file could not be written.
+ */
+pub fn export_series(path: &str, series: &TimeSeries) -> Result<(), String> {
+    let is_json = Path::new(path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| e == "jsonl" || e == "json");
+    write_series(path, series, is_json).map_err(|e| format!("cannot write series {}: {}", path, e))
+}
+
+fn write_series(path: &str, series: &TimeSeries, is_json: bool) -> std::io::Result<()> {
+    let mut out = BufWriter::new(File::create(path)?);
+    if !is_json {
+        writeln!(
+            out,
+            "time,throughput,mean_speed,queue_north,queue_east,queue_south,queue_west"
+        )?;
+    }
+
+    for sample in series.samples() {
+        if is_json {
+            serde_json::to_writer(&mut out, sample)?;
+            writeln!(out)?;
+            continue;
+        }
+        write!(
+            out,
+            "{},{},{}",
+            sample.time, sample.throughput, sample.mean_speed
+        )?;
+        for (_, queue) in sample.queues {
+            write!(out, ",{}", queue)?;
+        }
+        writeln!(out)?;
+    }
+    out.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sim::IntersectionGeometry;
+    use crate::sim::{Lane, Statistics};
+    use rand::{SeedableRng, rngs::StdRng};
+
+    fn sample(time: f32) -> Sample {
+        Sample {
+            time,
+            throughput: 7,
+            mean_speed: 45.5,
+            queues: [
+                (Direction::North, 1),
+                (Direction::East, 0),
+                (Direction::South, 3),
+                (Direction::West, 2),
+            ],
+        }
+    }
+
+    /// A fresh file name in the temporary directory.
+    fn temp_path(name: &str) -> String {
+        let path = std::env::temp_dir().join(format!("smart-road-{}-{}", std::process::id(), name));
+        path.to_str().unwrap().to_string()
+    }
+
+    #[test]
+    fn waiting_vehicles_count_as_stopped_in_the_mean_speed() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let lane = Lane::set(Direction::North, Direction::South);
+        let geometry = IntersectionGeometry::STANDARD;
+        let slot = geometry.lane_slots(&lane)[0];
+        let mut moving = Vehicle::new(0, lane, slot, &geometry, 0.0, &mut rng);
+        moving.speed = 60.0;
+        let mut waiting = Vehicle::new(1, lane, slot, &geometry, 0.0, &mut rng);
+        waiting.speed = 30.0;
+        waiting.state = VehicleState::Waiting;
+        let vehicles = [moving, waiting];
+
+        let live = Statistics::new().live(&vehicles, 5.0);
+        let sample = Sample::new(&vehicles, &live, 5.0);
+        assert_eq!(sample.mean_speed, 30.0);
+        assert_eq!(sample.total_queue(), 1);
+
+        let empty = Statistics::new().live(&[], 5.0);
+        assert_eq!(Sample::new(&[], &empty, 5.0).mean_speed, 0.0);
+    }
+
+    #[test]
+    fn the_oldest_samples_are_dropped_once_full() {
+        let mut series = TimeSeries::new();
+        assert!(series.is_empty());
+        for second in 0..SERIES_CAPACITY + 5 {
+            series.push(sample(second as f32));
+        }
+        assert_eq!(series.len(), SERIES_CAPACITY);
+        assert_eq!(series.samples().next().unwrap().time, 5.0);
+        assert_eq!(
+            series.samples().last().unwrap().time,
+            (SERIES_CAPACITY + 4) as f32
+        );
+    }
+
+    #[test]
+    fn exports_give_each_approach_queue_its_own_column_or_key() {
+        let mut series = TimeSeries::new();
+        series.push(sample(1.0));
+        series.push(sample(2.0));
+
+        let path = temp_path("series.csv");
+        export_series(&path, &series).unwrap();
+        let text = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(
+            lines,
+            [
+                "time,throughput,mean_speed,queue_north,queue_east,queue_south,queue_west",
+                "1,7,45.5,1,0,3,2",
+                "2,7,45.5,1,0,3,2",
+            ]
+        );
+
+        let path = temp_path("series.jsonl");
+        export_series(&path, &series).unwrap();
+        let text = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let first: serde_json::Value = serde_json::from_str(text.lines().next().unwrap()).unwrap();
+        assert_eq!(first["time"], 1.0);
+        assert_eq!(first["queues"]["south"], 3);
+        assert_eq!(text.lines().count(), 2);
+    }
+}
@@ -0,0 +1,213 @@
+use crate::sim::{
+    ConflictOccupancy, EventBus, IntersectionGeometry, IntersectionPolicy, RouteConflicts,
+    SafetyMonitor, SignalTimings, SimEvent,
+};
+use rand::{Rng, SeedableRng, rngs::StdRng};
+
+/// Number of simulation ticks that make up one simulated second.
+pub const TICKS_PER_SECOND: u64 = 60;
+
+/// Slowest and fastest time scale multipliers.
+pub const MIN_TIME_SCALE: f32 = 0.25;
+pub const MAX_TIME_SCALE: f32 = 16.0;
+
+pub struct Simulation {
+    pub next_vehicle_id: usize,
+    /// Number of fixed timesteps simulated so far.
+    pub tick: u64,
+    /// Length of one timestep in simulated seconds.
+    pub dt: f32,
+    /// When paused the clock only moves through explicitly requested steps.
+    pub paused: bool,
+    /// Simulated seconds per real second.
+    pub time_scale: f32,
+    /// Single steps requested while paused and not yet run.
+    pub pending_steps: u32,
+    /// Scaled real time not yet consumed by whole timesteps.
+    pub accumulator: f32,
+    /// Seed the RNG was created from, recorded so a run can be reproduced.
+    pub seed: u64,
+    /// Single source of randomness for routes, colors and speeds.
+    pub rng: StdRng,
+    /// Layout of the intersection new vehicles drive through.
+    pub geometry: IntersectionGeometry,
+    /// Which routes through the box meet, kept in step with `geometry` every tick.
+    pub route_conflicts: RouteConflicts,
+    /// Decides which vehicles may enter the intersection box.
+    pub policy: Box<dyn IntersectionPolicy>,
+    /// Signal durations a traffic light policy is built with.
+    pub signals: SignalTimings,
+    /// Whether the first collision halts the run: the window pauses, a headless run ends.
+    pub halt_on_collision: bool,
+    /// Set when a collision halted the run.
+    pub halted: bool,
+    /// Close call detection by time-to-collision and post-encroachment time.
+    pub safety: SafetyMonitor,
+    /// Subscribers to what happens during the run; nobody listens by default.
+    pub events: EventBus,
+}
+
+impl Default for Simulation {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Simulation {
+    /// Creates a simulation with a freshly drawn random seed.
+    pub fn new() -> Self {
+        Self::with_seed(rand::thread_rng().r#gen())
+    }
+
+    /// Creates a simulation whose random choices are fully determined by `seed`.
+    pub fn with_seed(seed: u64) -> Self {
+        Simulation {
+            next_vehicle_id: 0,
+            tick: 0,
+            dt: 1.0 / TICKS_PER_SECOND as f32,
+            paused: false,
+            time_scale: 1.0,
+            pending_steps: 0,
+            accumulator: 0.0,
+            seed,
+            rng: StdRng::seed_from_u64(seed),
+            geometry: IntersectionGeometry::STANDARD,
+            route_conflicts: RouteConflicts::new(&IntersectionGeometry::STANDARD),
+            policy: Box::new(ConflictOccupancy),
+            signals: SignalTimings::default(),
+            halt_on_collision: false,
+            halted: false,
+            safety: SafetyMonitor::default(),
+            events: EventBus::new(),
+        }
+    }
+
+    /// Simulated seconds elapsed since the start of the run.
+    pub fn time(&self) -> f32 {
+        self.tick as f32 * self.dt
+    }
+
+    /// Publishes `event`, stamped with the current tick and time.
+    pub fn emit(&mut self, event: SimEvent) {
+        let (tick, time) = (self.tick, self.time());
+        self.events.emit(tick, time, event);
+    }
+
+    /// Moves the clock forward by one timestep.
+    pub fn advance_clock(&mut self) {
+        self.tick += 1;
+    }
+
+    /**
+     * Works out how many timesteps to run for a frame that took `real_elapsed`
+     * real seconds, taking pause, single steps and the time scale into account.
+     */
+    pub fn steps_for_frame(&mut self, real_elapsed: f32) -> u32 {
+        if self.paused {
+            self.accumulator = 0.0;
+            return std::mem::take(&mut self.pending_steps);
+        }
+
+        self.accumulator += real_elapsed * self.time_scale;
+        let steps = (self.accumulator / self.dt) as u32;
+        self.accumulator -= steps as f32 * self.dt;
+        steps
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+        self.pending_steps = 0;
+    }
+
+    /// Pauses the clock and queues a single timestep.
+    pub fn request_step(&mut self) {
+        self.paused = true;
+        self.pending_steps += 1;
+    }
+
+    pub fn speed_up(&mut self) {
+        self.time_scale = (self.time_scale * 2.0).min(MAX_TIME_SCALE);
+    }
+
+    pub fn slow_down(&mut self) {
+        self.time_scale = (self.time_scale / 2.0).max(MIN_TIME_SCALE);
+    }
+
+    /// Short description of the clock mode, e.g. "Running 2x" or "Paused".
+    pub fn clock_mode(&self) -> String {
+        if self.paused && self.halted {
+            "Paused by a collision".to_string()
+        } else if self.paused {
+            "Paused".to_string()
+        } else {
+            format!("Running {}x", self.time_scale)
+        }
+    }
+
+    pub fn get_next_vehicle_id(&mut self) -> usize {
+        let id = self.next_vehicle_id;
+        self.next_vehicle_id += 1;
+        id
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn time_advances_one_fixed_step_per_tick() {
+        let mut simulation = Simulation::with_seed(0);
+        assert_eq!(simulation.time(), 0.0);
+        for _ in 0..TICKS_PER_SECOND * 3 {
+            simulation.advance_clock();
+        }
+        assert_eq!(simulation.tick, 180);
+        assert!((simulation.time() - 3.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn frames_run_the_scaled_time_in_whole_steps() {
+        let mut simulation = Simulation::with_seed(0);
+        let dt = simulation.dt;
+        assert_eq!(simulation.steps_for_frame(dt * 2.6), 2);
+        // The leftover part of a step carries over to the next frame
+        assert_eq!(simulation.steps_for_frame(dt * 0.6), 1);
+
+        simulation.speed_up();
+        simulation.speed_up();
+        assert_eq!(simulation.clock_mode(), "Running 4x");
+        assert_eq!(simulation.steps_for_frame(dt * 1.1), 4);
+    }
+
+    #[test]
+    fn time_scale_stays_within_its_limits() {
+        let mut simulation = Simulation::with_seed(0);
+        for _ in 0..10 {
+            simulation.speed_up();
+        }
+        assert_eq!(simulation.time_scale, MAX_TIME_SCALE);
+        for _ in 0..20 {
+            simulation.slow_down();
+        }
+        assert_eq!(simulation.time_scale, MIN_TIME_SCALE);
+    }
+
+    #[test]
+    fn a_paused_clock_only_runs_requested_steps() {
+        let mut simulation = Simulation::with_seed(0);
+        simulation.toggle_pause();
+        assert_eq!(simulation.clock_mode(), "Paused");
+        assert_eq!(simulation.steps_for_frame(1.0), 0);
+
+        simulation.request_step();
+        simulation.request_step();
+        assert_eq!(simulation.steps_for_frame(1.0), 2);
+        assert_eq!(simulation.steps_for_frame(1.0), 0);
+
+        simulation.request_step();
+        simulation.toggle_pause();
+        assert!(!simulation.paused);
+        assert_eq!(simulation.pending_steps, 0);
+    }
+}
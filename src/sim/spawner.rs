@@ -0,0 +1,240 @@
+use crate::sim::types::{Direction, VehicleColor};
+use crate::sim::{
+    Lane, MAX_SPEED, Scenario, ScenarioPlayer, SimEvent, Simulation, VEHICLE_SIZE, Vehicle,
+    random_destination,
+};
+use rand::Rng;
+
+/// Distance along the route a new vehicle keeps from the one ahead in its lane: a vehicle
+/// length of clear road between them.
+const SPAWN_GAP: f32 = 2.0 * VEHICLE_SIZE;
+
+/// Spawn requests for the current tick, raised by the keyboard, the random mode or a scenario.
+pub struct Spawner {
+    pub spawn_north: bool,
+    pub spawn_south: bool,
+    pub spawn_east: bool,
+    pub spawn_west: bool,
+    pub spawn_random: bool,
+    pub random_interval_ms: u64,
+    /// Simulation time (s) of the last random spawn.
+    pub random_last: f32,
+    /// Scenario being played back, if any.
+    pub scenario: Option<ScenarioPlayer>,
+}
+
+impl Default for Spawner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Spawner {
+    pub fn new() -> Self {
+        Spawner {
+            spawn_north: false,
+            spawn_south: false,
+            spawn_east: false,
+            spawn_west: false,
+            spawn_random: false,
+            random_interval_ms: 500,
+            random_last: 0.0,
+            scenario: None,
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.spawn_north = false;
+        self.spawn_south = false;
+        self.spawn_east = false;
+        self.spawn_west = false;
+        // self.spawn_random = false;
+    }
+
+    /// Clears the spawn flags and raises a single one for a random approach.
+    pub fn pick_random_direction<R: Rng>(&mut self, rng: &mut R) {
+        self.reset();
+
+        match rng.gen_range(0..4) {
+            0 => self.spawn_north = true,
+            1 => self.spawn_south = true,
+            2 => self.spawn_east = true,
+            _ => self.spawn_west = true,
+        }
+    }
+
+    /// Replaces any running scenario; its spawns come on top of the keyboard and random ones.
+    pub fn load_scenario(&mut self, scenario: Scenario) {
+        self.scenario = Some(ScenarioPlayer::new(scenario));
+    }
+
+    /**
+     * Puts a new vehicle making the movement `lane` at the start of one of
+     * the inbound lanes allowed for it, trying the lanes with the fewest
+     * vehicles before the box first.
+     *
+     * # Returns
+     *
+     * Whether the vehicle was spawned; not when every allowed entry is blocked.
+     */
+    fn spawn_in_lane(
+        lane: Lane,
+        color: Option<VehicleColor>,
+        speed: Option<f32>,
+        vehicles: &mut Vec<Vehicle>,
+        simulation: &mut Simulation,
+    ) -> bool {
+        let mut slots = simulation.geometry.lane_slots(&lane);
+        slots.sort_by_key(|&slot| {
+            vehicles
+                .iter()
+                .filter(|v| {
+                    v.lane.from == lane.from && v.slot == slot && v.box_enter_time.is_none()
+                })
+                .count()
+        });
+        let Some(slot) = slots
+            .into_iter()
+            .find(|&slot| Self::is_lane_spawn_safe(&lane, slot, vehicles))
+        else {
+            return false;
+        };
+        let (x, y) = simulation.geometry.spawn_point(&lane, slot);
+
+        let mut vehicle = Vehicle::new(
+            simulation.get_next_vehicle_id(),
+            lane,
+            slot,
+            &simulation.geometry,
+            simulation.time(),
+            &mut simulation.rng,
+        );
+        if let Some(color) = color {
+            vehicle.color = color;
+        }
+        if let Some(speed) = speed {
+            vehicle.speed = speed.min(MAX_SPEED);
+            vehicle.max_speed_reached = vehicle.speed;
+            vehicle.min_speed_reached = vehicle.speed;
+        }
+        simulation.emit(SimEvent::Spawned {
+            id: vehicle.id,
+            lane,
+            x,
+            y,
+            speed: vehicle.speed,
+        });
+        vehicles.push(vehicle);
+        true
+    }
+
+    /**
+     * Whether a vehicle can enter inbound lane `slot` of the approach of
+     * `lane`: every vehicle already in that lane is at least `SPAWN_GAP`
+     * along its route, a new vehicle starting at its beginning.
+     */
+    fn is_lane_spawn_safe(lane: &Lane, slot: usize, vehicles: &[Vehicle]) -> bool {
+        vehicles
+            .iter()
+            .filter(|v| v.lane.from == lane.from && v.slot == slot)
+            .all(|v| v.distance_travelled >= SPAWN_GAP)
+    }
+
+    pub fn spawn_cars(&mut self, vehicles: &mut Vec<Vehicle>, simulation: &mut Simulation) {
+        // function for random
+        if self.spawn_random {
+            let now = simulation.time();
+            if now - self.random_last >= self.random_interval_ms as f32 / 1000.0 {
+                self.pick_random_direction(&mut simulation.rng);
+                self.random_last = now;
+            }
+        }
+
+        // Arrow keys and random mode, in the order south, north, east, west
+        for (requested, from) in [
+            (self.spawn_south, Direction::South),
+            (self.spawn_north, Direction::North),
+            (self.spawn_east, Direction::East),
+            (self.spawn_west, Direction::West),
+        ] {
+            if requested {
+                let to = random_destination(from, &mut simulation.rng);
+                let lane = Lane::set(from, to);
+                if !Self::spawn_in_lane(lane, None, None, vehicles, simulation) {
+                    simulation.emit(SimEvent::SpawnDeferred { lane });
+                }
+            }
+        }
+
+        // Scenario spawns stay queued until their lane entry is clear
+        if let Some(player) = &mut self.scenario {
+            player.queue_due(simulation.time(), &mut simulation.rng);
+
+            let mut blocked: Vec<Lane> = Vec::new();
+            player.pending.retain_mut(|planned| {
+                if !blocked.contains(&planned.lane)
+                    && Self::spawn_in_lane(
+                        planned.lane,
+                        planned.color,
+                        planned.speed,
+                        vehicles,
+                        simulation,
+                    )
+                {
+                    return false;
+                }
+                // Later spawns for the same lane wait behind this one
+                blocked.push(planned.lane);
+                if !planned.deferred {
+                    simulation.emit(SimEvent::SpawnDeferred { lane: planned.lane });
+                    planned.deferred = true;
+                }
+                true
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_lane_takes_a_new_vehicle_once_the_last_one_is_a_vehicle_length_clear() {
+        let mut simulation = Simulation::with_seed(0);
+        let lane = Lane::set(Direction::North, Direction::South);
+        let slot = simulation.geometry.lane_slots(&lane)[0];
+        let mut vehicles = Vec::new();
+        assert!(Spawner::spawn_in_lane(
+            lane,
+            None,
+            None,
+            &mut vehicles,
+            &mut simulation
+        ));
+        assert_eq!(vehicles[0].slot, slot);
+
+        vehicles[0].travel(SPAWN_GAP - 1.0);
+        assert!(!Spawner::is_lane_spawn_safe(&lane, slot, &vehicles));
+        assert!(!Spawner::spawn_in_lane(
+            lane,
+            None,
+            None,
+            &mut vehicles,
+            &mut simulation
+        ));
+        vehicles[0].travel(1.0);
+        assert!(Spawner::spawn_in_lane(
+            lane,
+            None,
+            None,
+            &mut vehicles,
+            &mut simulation
+        ));
+        assert_eq!(vehicles.len(), 2);
+
+        // Other approaches and lanes are not in the way
+        let east = Lane::set(Direction::East, Direction::West);
+        assert!(Spawner::is_lane_spawn_safe(&east, slot, &vehicles));
+    }
+}
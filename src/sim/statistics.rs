@@ -0,0 +1,372 @@
+use crate::sim::types::Direction;
+use crate::sim::vehicle::Vehicle;
+use crate::sim::{
+    Breakdowns, CollisionRecord, Conflict, Distribution, Journey, Sample, TimeSeries, is_queued,
+};
+use serde::Serialize;
+
+/// Window for the live throughput figure (simulated seconds).
+const THROUGHPUT_WINDOW: f32 = 60.0;
+
+/// A snapshot of the running simulation, for the live overlay.
+#[derive(Clone, Debug)]
+pub struct LiveStats {
+    pub on_screen: usize,
+    pub completed: usize,
+    /// Vehicles that left the screen during the last simulated minute.
+    pub last_minute: usize,
+    /// Vehicles queued before the box right now, per approach.
+    pub queues: [(Direction, usize); 4],
+    /// Pairs of vehicles that had a close call so far.
+    pub close_calls: usize,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Statistics {
+    pub max_vehicles_passed: usize,
+    pub max_velocity: f32,
+    pub min_velocity: f32,
+    pub max_crossing_time: f32,
+    pub min_crossing_time: f32,
+    /// Pairs of vehicles that had a close call, see `conflicts`.
+    pub close_calls: usize,
+    /// Pairs of vehicles whose footprints overlapped, see `collision_records`.
+    pub collisions: usize,
+    /// Spawn to exit time of each vehicle (s).
+    pub crossing_time: Distribution,
+    /// Average speed of each vehicle over its journey (px/s).
+    pub speed: Distribution,
+    /// Time each vehicle spent waiting (s).
+    pub waiting_time: Distribution,
+    pub mean_box_time: f32,
+    pub mean_delay: f32,
+    /// Metrics per movement and per approach.
+    pub breakdowns: Breakdowns,
+    /// One sample per simulated second, the most recent hour of them.
+    pub series: TimeSeries,
+    /// One record per vehicle that left the screen, in order of leaving.
+    pub journeys: Vec<Journey>,
+    /// Every close call, in order of detection.
+    pub conflicts: Vec<Conflict>,
+    /// Every collision, in order of detection.
+    pub collision_records: Vec<CollisionRecord>,
+}
+
+impl Default for Statistics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Statistics {
+    pub fn new() -> Self {
+        Statistics {
+            max_vehicles_passed: 0,
+            max_velocity: 0.0,
+            min_velocity: 0.0,
+            max_crossing_time: 0.0,
+            min_crossing_time: 0.0,
+            close_calls: 0,
+            collisions: 0,
+            crossing_time: Distribution::default(),
+            speed: Distribution::default(),
+            waiting_time: Distribution::default(),
+            mean_box_time: 0.0,
+            mean_delay: 0.0,
+            breakdowns: Breakdowns::new(),
+            series: TimeSeries::new(),
+            journeys: Vec::new(),
+            conflicts: Vec::new(),
+            collision_records: Vec::new(),
+        }
+    }
+
+    pub fn calculate_intersection_stats(&mut self, completed_vehicles: &[Vehicle]) {
+        // Nothing changes until another vehicle completes
+        if completed_vehicles.len() == self.max_vehicles_passed {
+            return;
+        }
+
+        // Max vehicles that passed the intersection
+        self.max_vehicles_passed = completed_vehicles.len();
+
+        // Calculate velocity stats from all vehicles
+        let max_speeds: Vec<f32> = completed_vehicles
+            .iter()
+            .map(|v| v.max_speed_reached)
+            .collect();
+        let min_speeds: Vec<f32> = completed_vehicles
+            .iter()
+            .map(|v| v.min_speed_reached)
+            .collect();
+
+        if !max_speeds.is_empty() {
+            self.max_velocity = max_speeds.iter().fold(0.0f32, |a, &b| a.max(b));
+        }
+        if !min_speeds.is_empty() {
+            self.min_velocity = min_speeds.iter().fold(f32::MAX, |a, &b| a.min(b));
+        }
+
+        // Crossing times come from the journeys, like the histogram drawn of them
+        let crossing_times = self.crossing_times();
+
+        if !crossing_times.is_empty() {
+            self.max_crossing_time = crossing_times.iter().fold(0.0f32, |a, &b| a.max(b));
+            self.min_crossing_time = crossing_times.iter().fold(f32::MAX, |a, &b| a.min(b));
+        }
+
+        // Journey distributions and averages
+        self.crossing_time = Distribution::from_values(&crossing_times);
+        self.speed = Distribution::from_values(&self.average_speeds());
+        self.waiting_time = Distribution::from_values(&self.waiting_times());
+
+        let mean = |values: Vec<f32>| {
+            if values.is_empty() {
+                0.0
+            } else {
+                values.iter().sum::<f32>() / values.len() as f32
+            }
+        };
+        self.mean_box_time = mean(self.journeys.iter().filter_map(|j| j.box_time).collect());
+        self.mean_delay = mean(self.journeys.iter().map(|j| j.delay).collect());
+
+        self.breakdowns.update_journeys(&self.journeys);
+    }
+
+    pub fn crossing_times(&self) -> Vec<f32> {
+        self.journeys.iter().map(|j| j.travel_time).collect()
+    }
+
+    pub fn average_speeds(&self) -> Vec<f32> {
+        self.journeys.iter().map(|j| j.average_speed).collect()
+    }
+
+    pub fn waiting_times(&self) -> Vec<f32> {
+        self.journeys.iter().map(|j| j.waiting_time).collect()
+    }
+
+    /// The live figures for `vehicles`, the vehicles on screen at simulation time `now`.
+    pub fn live(&self, vehicles: &[Vehicle], now: f32) -> LiveStats {
+        let queue = |from: Direction| {
+            vehicles
+                .iter()
+                .filter(|v| v.lane.from == from && is_queued(v))
+                .count()
+        };
+        LiveStats {
+            on_screen: vehicles.len(),
+            completed: self.max_vehicles_passed,
+            last_minute: self
+                .journeys
+                .iter()
+                .rev()
+                .take_while(|j| j.exit_time > now - THROUGHPUT_WINDOW)
+                .count(),
+            queues: [
+                Direction::North,
+                Direction::East,
+                Direction::South,
+                Direction::West,
+            ]
+            .map(|from| (from, queue(from))),
+            close_calls: self.close_calls,
+        }
+    }
+
+    /// Adds a close call between two vehicles, live or completed.
+    pub fn record_conflict(&mut self, conflict: Conflict) {
+        self.breakdowns.add_conflict(&conflict);
+        self.conflicts.push(conflict);
+        self.close_calls = self.conflicts.len();
+    }
+
+    /**
+     * Adds a collision unless the same pair of vehicles collided before.
+     *
+     * # Returns
+     *
+     * Whether the collision was new.
+     */
+    pub fn record_collision(&mut self, record: CollisionRecord) -> bool {
+        if self
+            .collision_records
+            .iter()
+            .any(|known| known.vehicles == record.vehicles)
+        {
+            return false;
+        }
+        self.collision_records.push(record);
+        self.collisions = self.collision_records.len();
+        true
+    }
+
+    /// Adds a sample of `vehicles` at simulation time `now` to the time series.
+    pub fn record_sample(&mut self, vehicles: &[Vehicle], now: f32) {
+        let live = self.live(vehicles, now);
+        self.series.push(Sample::new(vehicles, &live, now));
+    }
+
+    /// Journeys with the largest delay first, at most `count` of them.
+    pub fn most_delayed(&self, count: usize) -> Vec<&Journey> {
+        let mut journeys: Vec<&Journey> = self.journeys.iter().collect();
+        journeys.sort_by(|a, b| b.delay.total_cmp(&a.delay));
+        journeys.truncate(count);
+        journeys
+    }
+
+    /// One line per distribution: mean, percentiles and standard deviation.
+    pub fn distribution_lines(&self) -> Vec<(&'static str, Distribution)> {
+        vec![
+            ("Crossing time (s)", self.crossing_time),
+            ("Speed (px/s)", self.speed),
+            ("Waiting time (s)", self.waiting_time),
+        ]
+    }
+
+    /// Human readable lines describing the statistics, one metric per line.
+    pub fn summary_lines(&self) -> Vec<String> {
+        vec![
+            format!(
+                "Max Vehicles Passed the intersection: {}",
+                self.max_vehicles_passed
+            ),
+            format!("Max Velocity: {:.2} px/s", self.max_velocity),
+            format!("Min Velocity: {:.2} px/s", self.min_velocity),
+            format!("Max Crossing Time: {:.2} s", self.max_crossing_time),
+            format!("Min Crossing Time: {:.2} s", self.min_crossing_time),
+            format!("Close Calls: {}", self.close_calls),
+            format!("Collisions: {}", self.collisions),
+            format!("Mean Waiting Time: {:.2} s", self.waiting_time.mean),
+            format!("Mean Time in Intersection: {:.2} s", self.mean_box_time),
+            format!("Mean Delay: {:.2} s", self.mean_delay),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sim::IntersectionGeometry;
+    use crate::sim::Lane;
+    use crate::sim::types::{Direction, VehicleState};
+    use rand::{SeedableRng, rngs::StdRng};
+
+    fn journey(id: usize, waiting_time: f32, box_time: Option<f32>, delay: f32) -> Journey {
+        Journey {
+            id,
+            lane: Lane::set(Direction::North, Direction::South),
+            spawn_time: 0.0,
+            stop_line_time: None,
+            waiting_time,
+            box_time,
+            exit_time: 10.0,
+            travel_time: 10.0,
+            free_flow_time: 10.0 - delay,
+            delay,
+            average_speed: 1.0,
+            close_call: false,
+        }
+    }
+
+    #[test]
+    fn journey_means_skip_missing_box_times() {
+        let mut statistics = Statistics::new();
+        statistics.journeys = vec![
+            journey(0, 1.0, Some(2.0), 3.0),
+            journey(1, 3.0, None, 5.0),
+            journey(2, 2.0, Some(4.0), 1.0),
+        ];
+        let mut rng = StdRng::seed_from_u64(0);
+        let lane = Lane::set(Direction::North, Direction::South);
+        let geometry = IntersectionGeometry::STANDARD;
+        let slot = geometry.lane_slots(&lane)[0];
+        let vehicle = Vehicle::new(0, lane, slot, &geometry, 0.0, &mut rng);
+        statistics.calculate_intersection_stats(&[vehicle]);
+        assert_eq!(statistics.waiting_time.mean, 2.0);
+        assert_eq!(statistics.mean_box_time, 3.0);
+        assert_eq!(statistics.mean_delay, 3.0);
+    }
+
+    #[test]
+    fn crossing_times_come_from_the_journeys() {
+        let mut statistics = Statistics::new();
+        statistics.journeys = vec![journey(0, 0.0, None, 0.0), journey(1, 0.0, None, 0.0)];
+        statistics.journeys[1].travel_time = 14.0;
+        let mut rng = StdRng::seed_from_u64(0);
+        let lane = Lane::set(Direction::North, Direction::South);
+        let geometry = IntersectionGeometry::STANDARD;
+        let slot = geometry.lane_slots(&lane)[0];
+        let vehicles = [
+            Vehicle::new(0, lane, slot, &geometry, 0.0, &mut rng),
+            Vehicle::new(1, lane, slot, &geometry, 0.0, &mut rng),
+        ];
+        statistics.calculate_intersection_stats(&vehicles);
+        assert_eq!(statistics.crossing_time.count, 2);
+        assert_eq!(statistics.crossing_time.mean, 12.0);
+        assert_eq!(
+            (statistics.min_crossing_time, statistics.max_crossing_time),
+            (10.0, 14.0)
+        );
+        assert_eq!(statistics.crossing_times(), [10.0, 14.0]);
+    }
+
+    #[test]
+    fn most_delayed_lists_the_longest_delays_first() {
+        let mut statistics = Statistics::new();
+        statistics.journeys = vec![
+            journey(0, 0.0, None, 3.0),
+            journey(1, 0.0, None, 5.0),
+            journey(2, 0.0, None, 1.0),
+        ];
+        let ids: Vec<usize> = statistics.most_delayed(2).iter().map(|j| j.id).collect();
+        assert_eq!(ids, [1, 0]);
+        assert_eq!(statistics.most_delayed(10).len(), 3);
+    }
+
+    #[test]
+    fn live_figures_count_the_last_minute_and_the_queues_on_screen() {
+        let mut statistics = Statistics::new();
+        for (id, exit_time) in [(0, 30.0), (1, 50.0), (2, 95.0), (3, 100.0)] {
+            let mut journey = journey(id, 0.0, None, 0.0);
+            journey.exit_time = exit_time;
+            statistics.journeys.push(journey);
+        }
+        statistics.max_vehicles_passed = 4;
+        statistics.close_calls = 1;
+
+        let mut rng = StdRng::seed_from_u64(0);
+        let lane = Lane::set(Direction::North, Direction::South);
+        let geometry = IntersectionGeometry::STANDARD;
+        let slot = geometry.lane_slots(&lane)[0];
+        let mut queued = Vehicle::new(4, lane, slot, &geometry, 0.0, &mut rng);
+        queued.state = VehicleState::Waiting;
+        queued.had_close_call = true;
+        let moving = Vehicle::new(5, lane, slot, &geometry, 0.0, &mut rng);
+
+        let live = statistics.live(&[queued, moving], 100.0);
+        assert_eq!(live.on_screen, 2);
+        assert_eq!(live.completed, 4);
+        assert_eq!(live.last_minute, 3);
+        assert_eq!(live.queues[0], (Direction::North, 1));
+        assert_eq!(live.queues[1], (Direction::East, 0));
+        assert_eq!(live.close_calls, 1);
+    }
+
+    #[test]
+    fn a_colliding_pair_is_recorded_once() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let lane = Lane::set(Direction::North, Direction::South);
+        let geometry = IntersectionGeometry::STANDARD;
+        let slot = geometry.lane_slots(&lane)[0];
+        let a = Vehicle::new(0, lane, slot, &geometry, 0.0, &mut rng);
+        let b = Vehicle::new(1, lane, slot, &geometry, 0.0, &mut rng);
+        let c = Vehicle::new(2, lane, slot, &geometry, 0.0, &mut rng);
+
+        let mut statistics = Statistics::new();
+        assert!(statistics.record_collision(CollisionRecord::new(1.0, &a, &b)));
+        assert!(!statistics.record_collision(CollisionRecord::new(2.0, &b, &a)));
+        assert!(statistics.record_collision(CollisionRecord::new(2.0, &b, &c)));
+        assert_eq!(statistics.collisions, 2);
+        assert_eq!(statistics.collision_records[0].time, 1.0);
+    }
+}
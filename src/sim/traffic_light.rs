@@ -0,0 +1,474 @@
+use crate::sim::types::{Admission, Direction, VehicleState};
+use crate::sim::{IntersectionPolicy, Lane, Vehicle, World};
+use serde::{Deserialize, Serialize};
+
+/// Vehicles this close to their stop line count as queued for actuation (px).
+pub const DETECTION_DISTANCE: f32 = 100.0;
+
+/**
+ * Signal durations set on the command line or in a scenario, in seconds.
+ * Anything left unset keeps the default of the plan the policy runs.
+ */
+#[derive(Copy, Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SignalTimings {
+    /// Green of the through phases; their minimum green when actuated.
+    pub green: Option<f32>,
+    /// Green of the protected left phases; their minimum green when actuated.
+    pub left_green: Option<f32>,
+    pub yellow: Option<f32>,
+    pub all_red: Option<f32>,
+    /// Longest an actuated green is extended to.
+    pub max_green: Option<f32>,
+}
+
+impl SignalTimings {
+    /// These timings, with anything unset taken from `other`.
+    pub fn or(self, other: SignalTimings) -> SignalTimings {
+        SignalTimings {
+            green: self.green.or(other.green),
+            left_green: self.left_green.or(other.left_green),
+            yellow: self.yellow.or(other.yellow),
+            all_red: self.all_red.or(other.all_red),
+            max_green: self.max_green.or(other.max_green),
+        }
+    }
+
+    /**
+     * Checks the durations that are set: greens and yellow must be positive,
+     * all-red must not be negative.
+     *
+     * # Returns
+     *
+     * A Result with an error string naming the first bad duration.
+     */
+    pub fn validate(&self) -> Result<(), String> {
+        let positive = [
+            ("green", self.green),
+            ("left_green", self.left_green),
+            ("yellow", self.yellow),
+            ("max_green", self.max_green),
+        ];
+        for (name, time) in positive {
+            if time.is_some_and(|time| !time.is_finite() || time <= 0.0) {
+                return Err(format!("signal {} must last more than 0 s", name));
+            }
+        }
+        if self
+            .all_red
+            .is_some_and(|time| !time.is_finite() || time < 0.0)
+        {
+            return Err("signal all_red must not be negative".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum Signal {
+    Green,
+    Yellow,
+    Red,
+}
+
+/// One stage of the signal plan: the movements that get green together.
+#[derive(Debug, Clone)]
+pub struct Phase {
+    pub name: String,
+    pub green_lanes: Vec<Lane>,
+    /// Green duration for fixed-time control, and the minimum green when actuated (s).
+    pub green_time: f32,
+    pub yellow_time: f32,
+    /// Red for every lane after the yellow, letting the box clear (s).
+    pub all_red_time: f32,
+}
+
+/// Green extension settings for actuated control.
+#[derive(Copy, Clone, Debug)]
+pub struct Actuation {
+    /// Green is never held longer than this (s).
+    pub max_green: f32,
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+enum Stage {
+    Green,
+    Yellow,
+    AllRed,
+}
+
+/**
+ * Conventional signal controller cycling through a fixed list of phases.
+ *
+ * With `actuation` set, a phase's green runs for at least `green_time` and
+ * is then extended while vehicles are queued on its green lanes, up to
+ * `max_green`. Without it every phase runs for exactly `green_time`.
+ */
+#[derive(Debug, Clone)]
+pub struct TrafficLight {
+    pub phases: Vec<Phase>,
+    pub actuation: Option<Actuation>,
+    current: usize,
+    stage: Stage,
+    stage_start: f32,
+}
+
+impl TrafficLight {
+    /**
+     * Creates a controller starting on the green of the first phase.
+     *
+     * # Returns
+     *
+     * A Result with an error string if there are no phases, a green or
+     * yellow is not positive, an all-red is negative, or the maximum green
+     * of actuated control is shorter than a phase's minimum.
+     */
+    pub fn new(phases: Vec<Phase>, actuation: Option<Actuation>) -> Result<Self, String> {
+        if phases.is_empty() {
+            return Err("a traffic light needs at least one phase".to_string());
+        }
+        for phase in &phases {
+            let positive = |time: f32| time.is_finite() && time > 0.0;
+            if !positive(phase.green_time) || !positive(phase.yellow_time) {
+                return Err(format!(
+                    "{}: green and yellow must last more than 0 s",
+                    phase.name
+                ));
+            }
+            if !phase.all_red_time.is_finite() || phase.all_red_time < 0.0 {
+                return Err(format!("{}: all-red must not be negative", phase.name));
+            }
+            if let Some(actuation) = actuation
+                && (actuation.max_green.is_nan() || actuation.max_green < phase.green_time)
+            {
+                return Err(format!(
+                    "{}: maximum green {} s is shorter than the minimum green {} s",
+                    phase.name, actuation.max_green, phase.green_time
+                ));
+            }
+        }
+        Ok(TrafficLight {
+            phases,
+            actuation,
+            current: 0,
+            stage: Stage::Green,
+            stage_start: 0.0,
+        })
+    }
+
+    /**
+     * Standard plan: a through phase for each axis, each followed by a
+     * protected left phase for each of its approaches. Right turns run with
+     * their approach's through and left phases.
+     *
+     * Opposing left turns cross inside the box here, so they get separate phases.
+     */
+    pub fn standard_phases(
+        through_green: f32,
+        left_green: f32,
+        yellow_time: f32,
+        all_red_time: f32,
+    ) -> Vec<Phase> {
+        let phase = |name: &str, green_lanes: Vec<Lane>, green_time: f32| Phase {
+            name: name.to_string(),
+            green_lanes,
+            green_time,
+            yellow_time,
+            all_red_time,
+        };
+
+        let mut phases = Vec::new();
+        for (a, b) in [
+            (Direction::North, Direction::South),
+            (Direction::East, Direction::West),
+        ] {
+            let through: Vec<Lane> = Lane::ALL
+                .into_iter()
+                .filter(|lane| (lane.from == a || lane.from == b) && !lane.is_left_turn())
+                .collect();
+            phases.push(phase(
+                &format!("{:?}-{:?} through", a, b),
+                through,
+                through_green,
+            ));
+
+            for approach in [a, b] {
+                let lanes: Vec<Lane> = Lane::ALL
+                    .into_iter()
+                    .filter(|lane| lane.from == approach && !lane.is_straight())
+                    .collect();
+                phases.push(phase(&format!("{:?} left", approach), lanes, left_green));
+            }
+        }
+        phases
+    }
+
+    /// The standard plan on fixed greens of 10 s through and 4 s left, unless `timings` says otherwise.
+    pub fn fixed_time(timings: &SignalTimings) -> Result<Self, String> {
+        Self::new(Self::timed_phases(timings, 10.0, 4.0), None)
+    }
+
+    /// The standard plan on minimum greens of 5 s through and 2 s left, extended
+    /// up to 20 s, unless `timings` says otherwise.
+    pub fn actuated(timings: &SignalTimings) -> Result<Self, String> {
+        let max_green = timings.max_green.unwrap_or(20.0);
+        Self::new(
+            Self::timed_phases(timings, 5.0, 2.0),
+            Some(Actuation { max_green }),
+        )
+    }
+
+    fn timed_phases(timings: &SignalTimings, through_green: f32, left_green: f32) -> Vec<Phase> {
+        Self::standard_phases(
+            timings.green.unwrap_or(through_green),
+            timings.left_green.unwrap_or(left_green),
+            timings.yellow.unwrap_or(3.0),
+            timings.all_red.unwrap_or(1.0),
+        )
+    }
+
+    pub fn current_phase(&self) -> &Phase {
+        &self.phases[self.current]
+    }
+
+    /// Moves the plan forward to simulation time `now`.
+    pub fn update(&mut self, now: f32, vehicles: &[Vehicle]) {
+        let elapsed = now - self.stage_start;
+        let phase = &self.phases[self.current];
+
+        let next = match self.stage {
+            Stage::Green => {
+                let done = match self.actuation {
+                    Some(actuation) => {
+                        elapsed >= phase.green_time
+                            && (elapsed >= actuation.max_green || !self.has_demand(vehicles))
+                    }
+                    None => elapsed >= phase.green_time,
+                };
+                done.then_some(Stage::Yellow)
+            }
+            Stage::Yellow => (elapsed >= phase.yellow_time).then_some(Stage::AllRed),
+            Stage::AllRed => (elapsed >= phase.all_red_time).then_some(Stage::Green),
+        };
+
+        if let Some(stage) = next {
+            if stage == Stage::Green {
+                self.current = (self.current + 1) % self.phases.len();
+            }
+            self.stage = stage;
+            self.stage_start = now;
+        }
+    }
+
+    /// Vehicles queued on or approaching a lane that is green in the current phase.
+    fn has_demand(&self, vehicles: &[Vehicle]) -> bool {
+        let phase = self.current_phase();
+        vehicles.iter().any(|v| {
+            let distance = v.distance_to_stop_line();
+            phase.green_lanes.contains(&v.lane)
+                && matches!(v.state, VehicleState::Approaching | VehicleState::Waiting)
+                && distance > -2.0
+                && distance <= DETECTION_DISTANCE
+        })
+    }
+
+    pub fn signal(&self, lane: &Lane) -> Signal {
+        if !self.current_phase().green_lanes.contains(lane) {
+            return Signal::Red;
+        }
+        match self.stage {
+            Stage::Green => Signal::Green,
+            Stage::Yellow => Signal::Yellow,
+            Stage::AllRed => Signal::Red,
+        }
+    }
+}
+
+impl IntersectionPolicy for TrafficLight {
+    fn name(&self) -> &'static str {
+        if self.actuation.is_some() {
+            "actuated"
+        } else {
+            "fixed-time"
+        }
+    }
+
+    fn begin_tick(&mut self, world: &World) {
+        self.update(world.now, world.vehicles);
+    }
+
+    /// Only a green signal lets a vehicle past its stop line; yellow means stop.
+    fn admit(&mut self, vehicle: &Vehicle, _world: &World) -> Admission {
+        match self.signal(&vehicle.lane) {
+            Signal::Green => Admission::Go,
+            Signal::Yellow | Signal::Red => Admission::Stop,
+        }
+    }
+
+    fn signal_for(&self, lane: &Lane) -> Option<Signal> {
+        Some(self.signal(lane))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sim::{IntersectionGeometry, RouteConflicts};
+    use rand::{SeedableRng, rngs::StdRng};
+
+    /// Runs `light` from `start` to `end` in tenths of a second.
+    fn run(light: &mut TrafficLight, start: f32, end: f32, vehicles: &[Vehicle]) {
+        let tenths = |time: f32| (time * 10.0).round() as u32;
+        for tenth in tenths(start)..=tenths(end) {
+            light.update(tenth as f32 / 10.0, vehicles);
+        }
+    }
+
+    #[test]
+    fn standard_phases_give_every_lane_a_conflict_free_green() {
+        let phases = TrafficLight::standard_phases(10.0, 4.0, 3.0, 1.0);
+        for lane in Lane::ALL {
+            assert!(
+                phases.iter().any(|phase| phase.green_lanes.contains(&lane)),
+                "{:?} never gets green",
+                lane
+            );
+        }
+        for phase in &phases {
+            for a in &phase.green_lanes {
+                for b in &phase.green_lanes {
+                    assert!(
+                        a == b || !a.conflicts_with(b),
+                        "{}: {:?} and {:?}",
+                        phase.name,
+                        a,
+                        b
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn fixed_time_runs_green_yellow_and_all_red_before_the_next_phase() {
+        let mut light = TrafficLight::fixed_time(&SignalTimings::default()).unwrap();
+        let through = light.phases[0].green_lanes[0];
+        assert_eq!(light.signal(&through), Signal::Green);
+
+        run(&mut light, 0.0, 9.9, &[]);
+        assert_eq!(light.signal(&through), Signal::Green);
+        run(&mut light, 10.0, 12.9, &[]);
+        assert_eq!(light.signal(&through), Signal::Yellow);
+        run(&mut light, 13.0, 13.9, &[]);
+        assert_eq!(light.signal(&through), Signal::Red);
+        assert_eq!(light.current_phase().name, light.phases[0].name);
+        run(&mut light, 14.0, 14.0, &[]);
+        assert_eq!(light.current_phase().name, light.phases[1].name);
+    }
+
+    #[test]
+    fn actuated_green_is_held_while_vehicles_queue_up_to_the_maximum() {
+        let mut light = TrafficLight::actuated(&SignalTimings::default()).unwrap();
+        let lane = Lane::set(Direction::North, Direction::South);
+        assert!(light.current_phase().green_lanes.contains(&lane));
+        let geometry = IntersectionGeometry::STANDARD;
+        let slot = geometry.lane_slots(&lane)[0];
+        let mut queued = Vehicle::new(0, lane, slot, &geometry, 0.0, &mut StdRng::seed_from_u64(0));
+        queued.travel(queued.route.stop_line - 50.0);
+
+        run(&mut light, 0.0, 19.9, std::slice::from_ref(&queued));
+        assert_eq!(light.signal(&lane), Signal::Green);
+        run(&mut light, 20.0, 20.0, &[queued]);
+        assert_eq!(light.signal(&lane), Signal::Yellow);
+
+        // Without demand the green ends at its minimum
+        let mut light = TrafficLight::actuated(&SignalTimings::default()).unwrap();
+        run(&mut light, 0.0, 5.0, &[]);
+        assert_eq!(light.signal(&lane), Signal::Yellow);
+    }
+
+    #[test]
+    fn configured_timings_replace_the_plan_defaults() {
+        let timings = SignalTimings {
+            green: Some(6.0),
+            yellow: Some(2.0),
+            all_red: Some(0.0),
+            ..SignalTimings::default()
+        };
+        let mut light = TrafficLight::fixed_time(&timings).unwrap();
+        let through = light.phases[0].green_lanes[0];
+        run(&mut light, 0.0, 5.9, &[]);
+        assert_eq!(light.signal(&through), Signal::Green);
+        run(&mut light, 6.0, 7.9, &[]);
+        assert_eq!(light.signal(&through), Signal::Yellow);
+        // No all-red left: the next phase follows on the next update
+        run(&mut light, 8.0, 8.1, &[]);
+        assert_eq!(light.current_phase().name, light.phases[1].name);
+        assert_eq!(light.phases[1].green_time, 4.0);
+
+        let timings = SignalTimings {
+            max_green: Some(8.0),
+            ..SignalTimings::default()
+        };
+        let light = TrafficLight::actuated(&timings).unwrap();
+        assert_eq!(light.actuation.unwrap().max_green, 8.0);
+        assert_eq!(light.phases[0].green_time, 5.0);
+    }
+
+    #[test]
+    fn rejects_plans_that_cannot_run() {
+        assert_eq!(
+            TrafficLight::new(Vec::new(), None).err().unwrap(),
+            "a traffic light needs at least one phase"
+        );
+        let phases = |yellow, all_red| TrafficLight::standard_phases(10.0, 4.0, yellow, all_red);
+        assert!(TrafficLight::new(phases(0.0, 1.0), None).is_err());
+        assert!(TrafficLight::new(phases(3.0, -1.0), None).is_err());
+        assert!(TrafficLight::new(phases(3.0, 0.0), None).is_ok());
+        let short = Some(Actuation { max_green: 8.0 });
+        assert!(TrafficLight::new(phases(3.0, 1.0), short).is_err());
+
+        let timings = SignalTimings {
+            green: Some(30.0),
+            ..SignalTimings::default()
+        };
+        assert!(TrafficLight::fixed_time(&timings).is_ok());
+        assert!(TrafficLight::actuated(&timings).is_err());
+        assert!(
+            SignalTimings {
+                yellow: Some(-1.0),
+                ..timings
+            }
+            .validate()
+            .is_err()
+        );
+        assert!(
+            SignalTimings {
+                all_red: Some(0.0),
+                ..timings
+            }
+            .validate()
+            .is_ok()
+        );
+    }
+
+    #[test]
+    fn only_green_admits() {
+        let mut light = TrafficLight::fixed_time(&SignalTimings::default()).unwrap();
+        let lane = light.phases[0].green_lanes[0];
+        let geometry = IntersectionGeometry::STANDARD;
+        let slot = geometry.lane_slots(&lane)[0];
+        let vehicle = Vehicle::new(0, lane, slot, &geometry, 0.0, &mut StdRng::seed_from_u64(0));
+
+        let routes = RouteConflicts::new(&geometry);
+        let world = World {
+            vehicles: &[],
+            routes: &routes,
+            now: 0.0,
+            dt: 1.0 / 60.0,
+        };
+        assert_eq!(light.admit(&vehicle, &world), Admission::Go);
+        run(&mut light, 0.0, 10.0, &[]);
+        assert_eq!(light.admit(&vehicle, &world), Admission::Stop);
+    }
+}
@@ -0,0 +1,292 @@
+use crate::sim::types::{Direction, VehicleState};
+use crate::sim::{Lane, Simulation, Vehicle};
+use serde::Serialize;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum TrajectoryFormat {
+    Csv,
+    JsonLines,
+}
+
+impl TrajectoryFormat {
+    pub fn from_name(name: &str) -> Result<Self, String> {
+        match name {
+            "csv" => Ok(TrajectoryFormat::Csv),
+            "jsonl" => Ok(TrajectoryFormat::JsonLines),
+            _ => Err(format!("unknown trajectory format: {} (csv|jsonl)", name)),
+        }
+    }
+
+    /// Format implied by a file name: JSON Lines for `.jsonl`/`.json`, CSV otherwise.
+    pub fn from_path(path: &str) -> Self {
+        match Path::new(path).extension().and_then(|e| e.to_str()) {
+            Some("jsonl" | "json") => TrajectoryFormat::JsonLines,
+            _ => TrajectoryFormat::Csv,
+        }
+    }
+}
+
+/// One vehicle at one sampled tick.
+#[derive(Serialize)]
+struct Sample {
+    tick: u64,
+    time: f32,
+    id: usize,
+    x: f32,
+    y: f32,
+    speed: f32,
+    /// Radians, clockwise from east.
+    heading: f32,
+    direction: Direction,
+    state: VehicleState,
+    lane: Lane,
+}
+
+/**
+ * Streams the state of every vehicle to CSV or JSON Lines, one row per
+ * vehicle per sampled tick.
+ *
+ * Rows hold the state after a step: `tick` counts the steps applied so far
+ * and `time` is the matching simulation time (s).
+ */
+pub struct TrajectoryWriter {
+    out: Box<dyn Write>,
+    format: TrajectoryFormat,
+    /// Only every `interval`-th tick is written.
+    interval: u64,
+    wrote_header: bool,
+}
+
+impl TrajectoryWriter {
+    pub fn new(out: Box<dyn Write>, format: TrajectoryFormat, interval: u64) -> Self {
+        TrajectoryWriter {
+            out,
+            format,
+            interval: interval.max(1),
+            wrote_header: false,
+        }
+    }
+
+    /**
+     * Opens a trajectory file.
+     *
+     * # Arguments
+     *
+     * * `path` - File to create, or `-` for stdout.
+     * * `format` - Output format, guessed from `path` when `None` (CSV for stdout).
+     * * `interval` - Sampling interval in ticks.
+     *
+     * # Returns
+     *
+     * A Result containing the writer or an error string.
+     */
+    pub fn create(
+        path: &str,
+        format: Option<TrajectoryFormat>,
+        interval: u64,
+    ) -> Result<Self, String> {
+        let format = format.unwrap_or_else(|| TrajectoryFormat::from_path(path));
+        let out: Box<dyn Write> = if path == "-" {
+            Box::new(std::io::stdout())
+        } else {
+            let file = File::create(path)
+                .map_err(|e| format!("cannot create trajectory file {}: {}", path, e))?;
+            Box::new(BufWriter::new(file))
+        };
+        Ok(Self::new(out, format, interval))
+    }
+
+    /// Writes the vehicles if the tick just completed falls on the sampling interval.
+    pub fn record(&mut self, simulation: &Simulation, vehicles: &[Vehicle]) -> Result<(), String> {
+        if !simulation.tick.is_multiple_of(self.interval) {
+            return Ok(());
+        }
+        self.write_rows(simulation.tick, simulation.time(), vehicles)
+            .map_err(|e| format!("cannot write trajectory: {}", e))
+    }
+
+    pub fn flush(&mut self) -> Result<(), String> {
+        self.out
+            .flush()
+            .map_err(|e| format!("cannot write trajectory: {}", e))
+    }
+
+    fn write_rows(&mut self, tick: u64, time: f32, vehicles: &[Vehicle]) -> std::io::Result<()> {
+        if self.format == TrajectoryFormat::Csv && !self.wrote_header {
+            writeln!(
+                self.out,
+                "tick,time,id,x,y,speed,heading,direction,state,from,to"
+            )?;
+            self.wrote_header = true;
+        }
+
+        for vehicle in vehicles {
+            match self.format {
+                TrajectoryFormat::Csv => writeln!(
+                    self.out,
+                    "{},{},{},{},{},{},{},{},{},{},{}",
+                    tick,
+                    time,
+                    vehicle.id,
+                    vehicle.x,
+                    vehicle.y,
+                    vehicle.speed,
+                    vehicle.heading,
+                    name(vehicle.direction),
+                    name(vehicle.state),
+                    name(vehicle.lane.from),
+                    name(vehicle.lane.to),
+                )?,
+                TrajectoryFormat::JsonLines => {
+                    let sample = Sample {
+                        tick,
+                        time,
+                        id: vehicle.id,
+                        x: vehicle.x,
+                        y: vehicle.y,
+                        speed: vehicle.speed,
+                        heading: vehicle.heading,
+                        direction: vehicle.direction,
+                        state: vehicle.state,
+                        lane: vehicle.lane,
+                    };
+                    serde_json::to_writer(&mut self.out, &sample)?;
+                    writeln!(self.out)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Lowercase name matching the JSON spelling of an enum value.
+fn name<T: std::fmt::Debug>(value: T) -> String {
+    format!("{:?}", value).to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sim::IntersectionGeometry;
+    use rand::{SeedableRng, rngs::StdRng};
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    /// Output shared with the test after the writer takes ownership of it.
+    #[derive(Clone, Default)]
+    struct Buffer(Rc<RefCell<Vec<u8>>>);
+
+    impl Write for Buffer {
+        fn write(&mut self, bytes: &[u8]) -> std::io::Result<usize> {
+            self.0.borrow_mut().extend_from_slice(bytes);
+            Ok(bytes.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl Buffer {
+        fn lines(&self) -> Vec<String> {
+            let text = String::from_utf8(self.0.borrow().clone()).unwrap();
+            text.lines().map(str::to_string).collect()
+        }
+    }
+
+    /// Records ticks `1..=ticks` of a single West -> East vehicle.
+    fn record(format: TrajectoryFormat, interval: u64, ticks: u64) -> Vec<String> {
+        let buffer = Buffer::default();
+        let mut writer = TrajectoryWriter::new(Box::new(buffer.clone()), format, interval);
+        let mut rng = StdRng::seed_from_u64(0);
+        let lane = Lane::set(Direction::West, Direction::East);
+        let geometry = IntersectionGeometry::STANDARD;
+        let slot = geometry.lane_slots(&lane)[0];
+        let mut vehicle = Vehicle::new(3, lane, slot, &geometry, 0.0, &mut rng);
+        (vehicle.x, vehicle.y) = (10.0, 437.5);
+        vehicle.speed = 60.0;
+        let mut simulation = Simulation::with_seed(0);
+        for _ in 0..ticks {
+            simulation.tick += 1;
+            writer
+                .record(&simulation, std::slice::from_ref(&vehicle))
+                .unwrap();
+        }
+        writer.flush().unwrap();
+        buffer.lines()
+    }
+
+    #[test]
+    fn csv_starts_with_a_header_and_writes_one_row_per_vehicle() {
+        let lines = record(TrajectoryFormat::Csv, 1, 2);
+        assert_eq!(lines.len(), 3);
+        assert_eq!(
+            lines[0],
+            "tick,time,id,x,y,speed,heading,direction,state,from,to"
+        );
+        let time = Simulation::with_seed(0).dt;
+        assert_eq!(
+            lines[1],
+            format!("1,{},3,10,437.5,60,0,east,approaching,west,east", time)
+        );
+        assert!(lines[2].starts_with("2,"));
+    }
+
+    #[test]
+    fn json_lines_use_the_serde_field_names() {
+        let lines = record(TrajectoryFormat::JsonLines, 1, 1);
+        assert_eq!(lines.len(), 1);
+        let sample: serde_json::Value = serde_json::from_str(&lines[0]).unwrap();
+        assert_eq!(sample["tick"], 1);
+        assert_eq!(sample["id"], 3);
+        assert_eq!(sample["x"], 10.0);
+        assert_eq!(sample["y"], 437.5);
+        assert_eq!(sample["speed"], 60.0);
+        assert_eq!(sample["heading"], 0.0);
+        assert_eq!(sample["direction"], "east");
+        assert_eq!(sample["state"], "approaching");
+        assert_eq!(sample["lane"]["from"], "west");
+        assert_eq!(sample["lane"]["to"], "east");
+        assert!(sample["time"].is_number());
+    }
+
+    #[test]
+    fn only_every_nth_tick_is_sampled() {
+        let lines = record(TrajectoryFormat::Csv, 5, 12);
+        let ticks: Vec<&str> = lines[1..]
+            .iter()
+            .map(|line| line.split(',').next().unwrap())
+            .collect();
+        assert_eq!(ticks, ["5", "10"]);
+        assert_eq!(record(TrajectoryFormat::JsonLines, 0, 2).len(), 2);
+    }
+
+    #[test]
+    fn format_follows_the_name_or_the_file_extension() {
+        assert_eq!(
+            TrajectoryFormat::from_path("out.jsonl"),
+            TrajectoryFormat::JsonLines
+        );
+        assert_eq!(
+            TrajectoryFormat::from_path("runs/out.json"),
+            TrajectoryFormat::JsonLines
+        );
+        assert_eq!(
+            TrajectoryFormat::from_path("out.csv"),
+            TrajectoryFormat::Csv
+        );
+        assert_eq!(TrajectoryFormat::from_path("out"), TrajectoryFormat::Csv);
+        assert_eq!(TrajectoryFormat::from_path("-"), TrajectoryFormat::Csv);
+        assert_eq!(
+            TrajectoryFormat::from_name("jsonl"),
+            Ok(TrajectoryFormat::JsonLines)
+        );
+        assert_eq!(
+            TrajectoryFormat::from_name("xml").unwrap_err(),
+            "unknown trajectory format: xml (csv|jsonl)"
+        );
+    }
+}
@@ -0,0 +1,87 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Direction {
+    North,
+    South,
+    East,
+    West,
+}
+
+impl Direction {
+    /// Unit vector pointing towards this side of the screen; y grows downwards.
+    pub fn unit(&self) -> (f32, f32) {
+        match self {
+            Direction::North => (0.0, -1.0),
+            Direction::South => (0.0, 1.0),
+            Direction::East => (1.0, 0.0),
+            Direction::West => (-1.0, 0.0),
+        }
+    }
+
+    /// The side a heading, in radians clockwise from east, points to the most.
+    pub fn nearest(heading: f32) -> Direction {
+        let (sin, cos) = heading.sin_cos();
+        if cos.abs() >= sin.abs() {
+            if cos > 0.0 {
+                Direction::East
+            } else {
+                Direction::West
+            }
+        } else if sin > 0.0 {
+            Direction::South
+        } else {
+            Direction::North
+        }
+    }
+
+    pub fn opposite(&self) -> Direction {
+        match self {
+            Direction::North => Direction::South,
+            Direction::South => Direction::North,
+            Direction::East => Direction::West,
+            Direction::West => Direction::East,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VehicleColor {
+    Blue,
+    Green,
+    Pink,
+    Yellow,
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VehicleState {
+    Approaching,
+    Waiting,
+    Crossing,
+    Exiting,
+}
+
+/// Whether a vehicle may enter the intersection box, decided each tick by the
+/// intersection control and read by `Vehicle::update`.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Admission {
+    /// Free to enter.
+    Go,
+    /// Must hold at the stop line.
+    Stop,
+    /// Cleared to enter from the given simulation time (s) onwards.
+    Reserved { arrival_time: f32 },
+}
+
+impl Admission {
+    pub fn may_enter(&self, now: f32, dt: f32) -> bool {
+        match *self {
+            Admission::Go => true,
+            Admission::Stop => false,
+            Admission::Reserved { arrival_time } => now + dt / 2.0 >= arrival_time,
+        }
+    }
+}
@@ -0,0 +1,309 @@
+use crate::sim::collision::Collision;
+use crate::sim::types::{Admission, Direction, VehicleColor, VehicleState};
+use crate::sim::{IntersectionGeometry, Lane, Route};
+
+use rand::Rng;
+
+// Speeds are in pixels per simulated second, accelerations in pixels per second squared
+pub const CRUISE_SPEED: f32 = 90.0;
+pub const MAX_SPEED: f32 = 120.0;
+pub const MIN_SPEED: f32 = 18.0;
+pub const ACCELERATION: f32 = 540.0;
+pub const DECELERATION: f32 = 720.0;
+
+/// Side of the square vehicle sprite, and of its footprint (px).
+pub const VEHICLE_SIZE: f32 = 30.0;
+
+#[derive(Debug, Clone)]
+pub struct Vehicle {
+    pub id: usize,
+    pub x: f32,
+    pub y: f32,
+    pub lane: Lane,
+    /// Inbound lane of its approach the vehicle drives in, counted from the road edge.
+    pub slot: usize,
+    /// The intersection the vehicle drives through.
+    pub geometry: IntersectionGeometry,
+    /// The path the vehicle drives, fixed by its lane.
+    pub route: Route,
+    /// Heading in radians, clockwise from east as the y axis points down the screen.
+    pub heading: f32,
+    /// The side of the screen the heading points to the most.
+    pub direction: Direction,
+    pub state: VehicleState,
+    pub color: VehicleColor,
+    pub speed: f32,
+    pub collision: Collision,
+    /// Simulation time (s) at which the vehicle was spawned, or handed on to
+    /// this intersection in a road network.
+    pub spawn_time: f32,
+    /// Simulated seconds between spawn and leaving the screen.
+    pub intersection_exit_time: Option<f32>,
+    pub max_speed_reached: f32,
+    pub min_speed_reached: f32,
+    pub had_close_call: bool,
+    /// Entry decision for the intersection box, refreshed by the traffic manager.
+    pub admission: Admission,
+    /// Simulation time (s) at which the vehicle first reached its stop line.
+    pub stop_line_time: Option<f32>,
+    /// Simulated seconds spent in `VehicleState::Waiting`.
+    pub waiting_time: f32,
+    /// Distance driven since spawning (px).
+    pub distance_travelled: f32,
+    /// Simulation times (s) at which the vehicle entered and left the intersection box.
+    pub box_enter_time: Option<f32>,
+    pub box_exit_time: Option<f32>,
+    /// Route distance of the nearest vehicle ahead past the edge of the map, on
+    /// the next intersection's approach; set by the road network.
+    pub leader_beyond_map: Option<f32>,
+}
+
+impl Vehicle {
+    /// A vehicle making the movement `lane` from inbound lane `slot`, just off the edge of the map.
+    pub fn new<R: Rng>(
+        id: usize,
+        lane: Lane,
+        slot: usize,
+        geometry: &IntersectionGeometry,
+        time: f32,
+        rng: &mut R,
+    ) -> Self {
+        let random_color = match rng.gen_range(0..4) {
+            0 => VehicleColor::Blue,
+            1 => VehicleColor::Green,
+            2 => VehicleColor::Pink,
+            _ => VehicleColor::Yellow,
+        };
+
+        let route = geometry.route(&lane, slot);
+        let (x, y) = geometry.spawn_point(&lane, slot);
+        let (_, heading) = route.pose_at(0.0);
+
+        let random_speed = rng.gen_range(48.0..72.0);
+
+        Vehicle {
+            id,
+            x,
+            y,
+            speed: random_speed,
+            color: random_color,
+            direction: Direction::nearest(heading),
+            heading,
+            geometry: *geometry,
+            route,
+            lane,
+            slot,
+            state: VehicleState::Approaching,
+            collision: Collision::new(),
+            spawn_time: time,
+            intersection_exit_time: None,
+            max_speed_reached: random_speed,
+            min_speed_reached: random_speed,
+            had_close_call: false,
+            admission: Admission::Stop,
+            stop_line_time: None,
+            waiting_time: 0.0,
+            distance_travelled: 0.0,
+            box_enter_time: None,
+            box_exit_time: None,
+            leader_beyond_map: None,
+        }
+    }
+
+    /**
+     * The vehicle carried on into the next intersection of a road network,
+     * making the movement `lane` from the same inbound lane slot and already
+     * `distance` along its new route. What it recorded about the intersection
+     * it left starts afresh, so each intersection measures its own pass.
+     */
+    pub fn hand_off(
+        &self,
+        lane: Lane,
+        geometry: &IntersectionGeometry,
+        distance: f32,
+        time: f32,
+    ) -> Self {
+        let mut vehicle = Vehicle {
+            lane,
+            geometry: *geometry,
+            route: geometry.route(&lane, self.slot),
+            state: VehicleState::Approaching,
+            spawn_time: time,
+            intersection_exit_time: None,
+            max_speed_reached: self.speed,
+            min_speed_reached: self.speed,
+            had_close_call: false,
+            admission: Admission::Stop,
+            stop_line_time: None,
+            waiting_time: 0.0,
+            distance_travelled: 0.0,
+            box_enter_time: None,
+            box_exit_time: None,
+            leader_beyond_map: None,
+            ..self.clone()
+        };
+        vehicle.travel(distance);
+        vehicle
+    }
+
+    pub fn distance_to_intersection(&self) -> f32 {
+        let (intersection_center_x, intersection_center_y) = self.geometry.centre();
+
+        // Calculate Euclidean distance to intersection center
+        ((self.x - intersection_center_x).powi(2) + (self.y - intersection_center_y).powi(2)).sqrt()
+    }
+
+    /// Whether the two vehicles drive in the same inbound lane.
+    pub fn shares_lane_with(&self, other: &Vehicle) -> bool {
+        self.lane.from == other.lane.from && self.slot == other.slot
+    }
+
+    /// Whether the two vehicles have both joined the same outbound lane.
+    pub fn shares_outbound_lane_with(&self, other: &Vehicle) -> bool {
+        let joined = |v: &Vehicle| v.distance_travelled >= v.route.joins_exit();
+        self.lane.to == other.lane.to && self.slot == other.slot && joined(self) && joined(other)
+    }
+
+    /// Centre of the footprint; `x`, `y` is its top-left corner.
+    pub fn centre(&self) -> (f32, f32) {
+        (self.x + VEHICLE_SIZE / 2.0, self.y + VEHICLE_SIZE / 2.0)
+    }
+
+    /// Distance along the route to the stop line, negative once past it.
+    pub fn distance_to_stop_line(&self) -> f32 {
+        self.route.stop_line - self.distance_travelled
+    }
+
+    /// Advances the vehicle by one fixed timestep of `dt` simulated seconds,
+    /// `now` being the simulation time at the start of the step.
+    pub fn update(&mut self, vehicles: &[Vehicle], now: f32, dt: f32) {
+        // Calculate target speed based on conditions
+        let mut target_speed = CRUISE_SPEED;
+        let distance_to_intersection = self.distance_to_intersection();
+        let distance_to_stop_line = self.distance_to_stop_line();
+
+        // Slow down when approaching intersection
+        if let (VehicleState::Approaching, Admission::Reserved { arrival_time }) =
+            (self.state, self.admission)
+        {
+            // Pace the approach so the stop line is reached at the reserved time
+            let time_left = arrival_time - now;
+            if time_left > dt {
+                target_speed = (distance_to_stop_line.max(0.0) / time_left).min(MAX_SPEED);
+            }
+        } else if self.state == VehicleState::Approaching {
+            let braking_distance = 140.0;
+            if distance_to_stop_line < braking_distance {
+                let braking_ratio = (distance_to_stop_line / braking_distance).max(0.2);
+                target_speed = CRUISE_SPEED * braking_ratio;
+            } else if distance_to_intersection < 150.0 {
+                let braking_ratio = (distance_to_intersection / 150.0).max(0.3);
+                target_speed = CRUISE_SPEED * braking_ratio;
+            }
+        }
+
+        // Check for intersection mutual exclusion (only if at boundary)
+        let in_intersection = Collision::is_vehicle_in_intersection(self);
+
+        if self.state == VehicleState::Approaching
+            && distance_to_stop_line <= 0.0
+            && !in_intersection
+        {
+            self.stop_line_time.get_or_insert(now);
+
+            if !self.admission.may_enter(now, dt) {
+                self.state = VehicleState::Waiting;
+                target_speed = 0.0;
+            }
+        }
+
+        // Check for vehicles ahead and adjust target speed
+        if let Some(distance) = self.gap_ahead(vehicles) {
+            if distance < self.collision.safe_distance {
+                self.state = VehicleState::Waiting;
+                target_speed = 0.0;
+            } else if distance < self.collision.safe_distance + 30.0 {
+                // Slow down proportionally based on distance
+                let ratio = (distance - self.collision.safe_distance) / 30.0;
+                target_speed = 48.0 * ratio + 24.0; // Range: 24 to 72
+            }
+        }
+
+        // Smoothly interpolate towards target speed
+        if target_speed > self.speed {
+            // Accelerate
+            self.speed = (self.speed + ACCELERATION * dt)
+                .min(target_speed)
+                .min(MAX_SPEED);
+        } else if target_speed < self.speed {
+            // Decelerate
+            self.speed = (self.speed - DECELERATION * dt)
+                .max(target_speed)
+                .max(MIN_SPEED);
+        }
+
+        // If waiting, don't move
+        if self.state == VehicleState::Waiting {
+            // Check if path is clear and intersection is available
+            let path_clear = self.gap_ahead(vehicles).is_none();
+            // Only a vehicle holding at the stop line needs clearance to move on
+            let held_at_stop_line =
+                distance_to_stop_line <= 0.0 && distance_to_stop_line > -10.0 && !in_intersection;
+            let intersection_available = !held_at_stop_line || self.admission.may_enter(now, dt);
+
+            if path_clear && intersection_available {
+                self.state = VehicleState::Approaching;
+            } else {
+                self.waiting_time += dt;
+                return;
+            }
+        }
+
+        self.advance(dt);
+
+        // Track journey times, as of the end of this step
+        if Collision::is_vehicle_in_intersection(self) {
+            self.box_enter_time.get_or_insert(now + dt);
+        } else if self.box_enter_time.is_some() && self.box_exit_time.is_none() {
+            self.box_exit_time = Some(now + dt);
+        }
+
+        // Track speed statistics
+        self.max_speed_reached = self.max_speed_reached.max(self.speed);
+        self.min_speed_reached = self.min_speed_reached.min(self.speed);
+    }
+
+    /// Distance to the vehicle ahead when close enough to follow, on the map or past its edge.
+    /// On the map it is the larger of the two axis offsets, so a leader stopped just round a
+    /// tight turn is not clipped at the corner.
+    fn gap_ahead(&self, vehicles: &[Vehicle]) -> Option<f32> {
+        let on_map = Collision::check_vehicle_ahead(self, vehicles)
+            .map(|ahead| (self.x - ahead.x).abs().max((self.y - ahead.y).abs()));
+        let beyond_map = self
+            .leader_beyond_map
+            .map(|distance| distance - self.distance_travelled)
+            .filter(|&gap| gap <= self.collision.safe_distance + 20.0);
+        on_map.into_iter().chain(beyond_map).reduce(f32::min)
+    }
+
+    /// Moves the vehicle along its route at its current speed.
+    pub fn advance(&mut self, dt: f32) {
+        self.travel(self.speed * dt);
+    }
+
+    /// Moves the vehicle `distance` further along its route, turning inside the box.
+    pub fn travel(&mut self, distance: f32) {
+        self.distance_travelled += distance;
+        let ((x, y), heading) = self.route.pose_at(self.distance_travelled);
+        self.x = x - VEHICLE_SIZE / 2.0;
+        self.y = y - VEHICLE_SIZE / 2.0;
+        self.heading = heading;
+        self.direction = Direction::nearest(heading);
+
+        if self.distance_travelled > self.route.box_exit {
+            self.state = VehicleState::Exiting;
+        } else if Collision::is_vehicle_in_intersection(self) {
+            self.state = VehicleState::Crossing;
+        }
+    }
+}
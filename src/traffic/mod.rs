@@ -1,8 +0,0 @@
-pub mod lane;
-pub use lane::*;
-
-pub mod collision;
-pub use collision::*;
-
-pub mod manager;
-pub use manager::*;